@@ -0,0 +1,16 @@
+use ccs_lint::linter::{to_json, Linter};
+use ccs_lint::rule::Rule;
+
+fn main() {
+    let source = include_str!("ccs_lint.ccs");
+    let program = ccs::ast::Program::try_from(source).unwrap();
+    for lint in Linter::new().lint_ccs(&program) {
+        print!("{}", lint.render(source, "ccs_lint.ccs"));
+    }
+
+    let source = include_str!("ccsvp_lint.ccsvp");
+    let program = ccs_vp::ast::Program::try_from(source).unwrap();
+    let linter = Linter::new().allow(Rule::UnreachableConstant);
+    let lints = linter.lint_ccs_vp(&program, source);
+    println!("{}", to_json(&lints, source, "ccsvp_lint.ccsvp"));
+}
@@ -0,0 +1,3 @@
+pub mod linter;
+pub mod rule;
+pub mod rules;
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+
+use ccs::diagnostic::{line_col, Diagnostic};
+use itertools::Itertools;
+
+use crate::rule::Rule;
+use crate::rules;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: Rule,
+    pub diagnostic: Diagnostic,
+}
+impl Lint {
+    pub fn render(&self, source: &str, file: &str) -> String {
+        let rendered = self.diagnostic.render(source, file);
+        format!("{rendered} = note: suppress with allow={}\n", self.rule)
+    }
+    pub fn to_json(&self, source: &str, file: &str) -> String {
+        let d = &self.diagnostic;
+        let location = match d.span {
+            Some(span) => {
+                let (line, column) = line_col(source, span.start);
+                format!(
+                    "\"line\":{line},\"column\":{column},\"start\":{},\"end\":{}",
+                    span.start, span.end
+                )
+            }
+            None => "\"line\":null,\"column\":null,\"start\":null,\"end\":null".to_string(),
+        };
+        let hint = d.hint.as_deref().map_or("null".to_string(), json_string);
+        format!(
+            "{{\"rule\":{},\"severity\":{},\"message\":{},\"file\":{},{location},\"hint\":{hint}}}",
+            json_string(self.rule.name()),
+            json_string(&d.severity.to_string()),
+            json_string(&d.message),
+            json_string(file),
+        )
+    }
+}
+
+pub fn to_json(lints: &[Lint], source: &str, file: &str) -> String {
+    format!(
+        "[{}]",
+        lints.iter().map(|l| l.to_json(source, file)).join(",")
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\t' => out += "\\t",
+            c if c.is_control() => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out + "\""
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Linter {
+    allowed: HashSet<Rule>,
+}
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn allow(mut self, rule: Rule) -> Self {
+        self.allowed.insert(rule);
+        self
+    }
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        !self.allowed.contains(&rule)
+    }
+    pub fn lint_ccs(&self, program: &ccs::ast::Program) -> Vec<Lint> {
+        self.collect(|rule| match rule {
+            Rule::UnreachableConstant => rules::unreachable_constants(program),
+            Rule::OneWayChannel => rules::one_way_channels(program),
+            Rule::IdenticalSummands => rules::identical_summands(program),
+            Rule::ShadowedVariable => vec![],
+            Rule::TauSubstitution => rules::tau_substitutions(program),
+        })
+    }
+    pub fn lint_ccs_vp(&self, program: &ccs_vp::ast::Program, source: &str) -> Vec<Lint> {
+        self.collect(|rule| match rule {
+            Rule::UnreachableConstant => rules::unreachable_constants_vp(program),
+            Rule::IdenticalSummands => rules::identical_summands_vp(program),
+            Rule::ShadowedVariable => rules::shadowed_variables(program, source),
+            Rule::OneWayChannel => vec![],
+            Rule::TauSubstitution => rules::tau_substitutions_vp(program),
+        })
+    }
+    fn collect(&self, run: impl Fn(Rule) -> Vec<Diagnostic>) -> Vec<Lint> {
+        Rule::ALL
+            .into_iter()
+            .filter(|rule| self.is_enabled(*rule))
+            .flat_map(|rule| {
+                run(rule)
+                    .into_iter()
+                    .map(move |diagnostic| Lint { rule, diagnostic })
+            })
+            .sorted_by_key(|l| l.diagnostic.span.map(|s| s.start))
+            .collect()
+    }
+}
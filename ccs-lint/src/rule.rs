@@ -0,0 +1,44 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    UnreachableConstant,
+    OneWayChannel,
+    IdenticalSummands,
+    ShadowedVariable,
+    TauSubstitution,
+}
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::UnreachableConstant,
+        Rule::OneWayChannel,
+        Rule::IdenticalSummands,
+        Rule::ShadowedVariable,
+        Rule::TauSubstitution,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::UnreachableConstant => "unreachable-constant",
+            Rule::OneWayChannel => "one-way-channel",
+            Rule::IdenticalSummands => "identical-summands",
+            Rule::ShadowedVariable => "shadowed-variable",
+            Rule::TauSubstitution => "tau-substitution",
+        }
+    }
+}
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+impl FromStr for Rule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|rule| rule.name() == s)
+            .ok_or_else(|| format!("unknown lint rule: {s}"))
+    }
+}
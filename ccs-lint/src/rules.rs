@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+
+use ccs::ast::{Command, Program, Statement};
+use ccs::check::{channel_table, channels};
+use ccs::context::Context;
+use ccs::diagnostic::{Diagnostic, Span};
+use ccs::process::{Channel, Process, Substitution};
+use ccs_vp::ast::{Program as ProgramVp, Statement as StatementVp};
+use ccs_vp::process::{Channel as ChannelVp, Process as ProcessVp};
+
+pub fn unreachable_constants(program: &Program) -> Vec<Diagnostic> {
+    let Ok(ctx) = Context::try_from(program.clone()) else {
+        return vec![];
+    };
+    let Some(main) = program.0.iter().find_map(|stmt| match &stmt.node {
+        Statement::Exec(Command::SetMain(main)) => Some(main),
+        _ => None,
+    }) else {
+        return vec![];
+    };
+    let calls = |name: &str| {
+        ctx.get_process(name)
+            .map(Process::free_vars)
+            .unwrap_or_default()
+    };
+    let reachable = reachable_from(main, calls);
+    program
+        .0
+        .iter()
+        .filter_map(|stmt| match &stmt.node {
+            Statement::DefConstant(name, _) if !reachable.contains(&name.node) => {
+                let message = format!("constant \"{name}\" is never used");
+                Some(Diagnostic::warning(message, Some(name.span)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn unreachable_constants_vp(program: &ProgramVp) -> Vec<Diagnostic> {
+    let constants = program
+        .0
+        .iter()
+        .filter_map(|stmt| match &stmt.node {
+            StatementVp::DefConstant(name, (_, p)) => Some((name.node.as_str(), p)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let Some(main) = program.0.iter().find_map(|stmt| match &stmt.node {
+        StatementVp::Exec(ccs_vp::ast::Command::SetMain(main)) => Some(main),
+        _ => None,
+    }) else {
+        return vec![];
+    };
+    let calls = |name: &str| {
+        let mut calls = HashSet::new();
+        if let Some(p) = constants.get(name) {
+            walk_vp(p, &mut |p| {
+                if let ProcessVp::Constant(x, ..) = p {
+                    calls.insert(x.clone());
+                }
+            });
+        }
+        calls
+    };
+    let reachable = reachable_from(main, calls);
+    program
+        .0
+        .iter()
+        .filter_map(|stmt| match &stmt.node {
+            StatementVp::DefConstant(name, _) if !reachable.contains(&name.node) => {
+                let message = format!("constant \"{name}\" is never used");
+                Some(Diagnostic::warning(message, Some(name.span)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn one_way_channels(program: &Program) -> Vec<Diagnostic> {
+    let Ok(ctx) = Context::try_from(program.clone()) else {
+        return vec![];
+    };
+    let table = channel_table(&ctx);
+    let mut diagnostics = vec![];
+    for stmt in &program.0 {
+        let Statement::DefConstant(_, p) = &stmt.node else {
+            continue;
+        };
+        walk(p, &mut |p| {
+            let Process::Restriction(q, names, loc) = p else {
+                return;
+            };
+            let used = channels(q, &table);
+            for x in names {
+                if ctx.is_broadcast(x) || ctx.buffer_of(x).is_some() {
+                    continue;
+                }
+                let send = used.contains(&Channel::send(x));
+                let recv = used.contains(&Channel::recv(x));
+                let message = match (send, recv) {
+                    (true, false) => {
+                        format!("channel \"{x}\" is only sent on within its restriction")
+                    }
+                    (false, true) => {
+                        format!("channel \"{x}\" is only received on within its restriction")
+                    }
+                    _ => continue,
+                };
+                diagnostics.push(
+                    Diagnostic::warning(message, loc.span())
+                        .with_hint("every action on this channel blocks forever"),
+                );
+            }
+        });
+    }
+    diagnostics
+}
+
+pub fn identical_summands(program: &Program) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for stmt in &program.0 {
+        let Statement::DefConstant(name, p) = &stmt.node else {
+            continue;
+        };
+        walk(p, &mut |p| {
+            if let Process::Sum(sum) = p {
+                diagnostics.extend(duplicates(sum, &name.node, stmt.span, Process::span));
+            }
+        });
+    }
+    diagnostics
+}
+
+pub fn identical_summands_vp(program: &ProgramVp) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for stmt in &program.0 {
+        let StatementVp::DefConstant(name, (_, p)) = &stmt.node else {
+            continue;
+        };
+        walk_vp(p, &mut |p| {
+            if let ProcessVp::Sum(sum) = p {
+                diagnostics.extend(duplicates(sum, &name.node, stmt.span, ProcessVp::span));
+            }
+        });
+    }
+    diagnostics
+}
+
+pub fn tau_substitutions(program: &Program) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for stmt in &program.0 {
+        let Statement::DefConstant(_, p) = &stmt.node else {
+            continue;
+        };
+        walk(p, &mut |p| {
+            if let Process::Substitution(_, subs) = p {
+                diagnostics.extend(tau_pairs(subs));
+            }
+        });
+    }
+    diagnostics
+}
+
+pub fn tau_substitutions_vp(program: &ProgramVp) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for stmt in &program.0 {
+        let StatementVp::DefConstant(_, (_, p)) = &stmt.node else {
+            continue;
+        };
+        walk_vp(p, &mut |p| {
+            if let ProcessVp::Substitution(_, subs) = p {
+                diagnostics.extend(tau_pairs(subs));
+            }
+        });
+    }
+    diagnostics
+}
+
+pub fn shadowed_variables(program: &ProgramVp, source: &str) -> Vec<Diagnostic> {
+    fn shadowed_rec(
+        p: &ProcessVp,
+        bound: &mut Vec<String>,
+        found: &mut Vec<(String, Option<Span>)>,
+    ) {
+        if let ProcessVp::Action(ChannelVp::Recv(_, Some(x)), q, loc) = p {
+            if bound.contains(x) {
+                found.push((x.clone(), loc.span()));
+            }
+            bound.push(x.clone());
+            shadowed_rec(q, bound, found);
+            bound.pop();
+            return;
+        }
+        for q in p.children() {
+            shadowed_rec(q, bound, found);
+        }
+    }
+
+    let mut diagnostics = vec![];
+    for stmt in &program.0 {
+        let StatementVp::DefConstant(_, (params, p)) = &stmt.node else {
+            continue;
+        };
+        let mut found = vec![];
+        shadowed_rec(p, &mut params.clone(), &mut found);
+        for (x, prefix) in found {
+            // the variable is the last name in its receive prefix
+            let span = prefix.and_then(|s| {
+                let start = s.start + source[s.start..s.end].rfind(x.as_str())?;
+                Some(Span::new(start, start + x.len()))
+            });
+            let message = format!("received variable \"{x}\" shadows an earlier binding");
+            diagnostics
+                .push(Diagnostic::warning(message, span).with_hint("rename the received variable"));
+        }
+    }
+    diagnostics
+}
+
+fn reachable_from(main: &str, calls: impl Fn(&str) -> HashSet<String>) -> HashSet<String> {
+    let mut reachable = HashSet::from([main.to_string()]);
+    let mut stack = vec![main.to_string()];
+    while let Some(name) = stack.pop() {
+        for x in calls(&name) {
+            if reachable.insert(x.clone()) {
+                stack.push(x);
+            }
+        }
+    }
+    reachable
+}
+
+fn duplicates<T: PartialEq + std::fmt::Display>(
+    sum: &[T],
+    name: &str,
+    span: Span,
+    span_of: impl Fn(&T) -> Option<Span>,
+) -> Vec<Diagnostic> {
+    sum.iter()
+        .enumerate()
+        .filter(|(i, p)| sum[..*i].contains(p))
+        .map(|(_, p)| {
+            let message = format!("summand \"{p}\" appears more than once in \"{name}\"");
+            Diagnostic::warning(message, span_of(p).or(Some(span)))
+                .with_hint("remove the repeated summand")
+        })
+        .collect()
+}
+
+fn tau_pairs(subs: &Substitution) -> Vec<Diagnostic> {
+    let tau = Channel::tau().name().to_string();
+    let span = subs.span();
+    subs.pairs()
+        .iter()
+        .filter_map(|(new, old)| {
+            if *old == tau {
+                let message = format!("substitution \"{new}/{tau}\" has no effect");
+                Some(Diagnostic::warning(message, span).with_hint("tau cannot be renamed"))
+            } else if *new == tau {
+                let message = format!("\"{old}\" is renamed to a visible channel called \"{tau}\"");
+                let hint = format!("hide the channel with \"/ {{{old}}}\" to make it silent");
+                Some(Diagnostic::warning(message, span).with_hint(hint))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn walk(p: &Process, f: &mut impl FnMut(&Process)) {
+    f(p);
+    for q in p.children() {
+        walk(q, f);
+    }
+}
+
+fn walk_vp(p: &ProcessVp, f: &mut impl FnMut(&ProcessVp)) {
+    f(p);
+    for q in p.children() {
+        walk_vp(q, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The source text each diagnostic covers.
+    fn covered<'a>(source: &'a str, diagnostics: &[Diagnostic]) -> Vec<&'a str> {
+        diagnostics
+            .iter()
+            .map(|d| {
+                let span = d.span.expect("diagnostic without a location");
+                &source[span.start..span.end]
+            })
+            .collect()
+    }
+
+    fn program(source: &str) -> Program {
+        Program::try_from(source).unwrap()
+    }
+
+    #[test]
+    fn one_way_channel_points_at_its_restriction() {
+        let source = "#![start(Main)]\nfn Main() { (req!.nil | (req!.nil \\ {req})) }";
+        let diagnostics = one_way_channels(&program(source));
+        assert_eq!(covered(source, &diagnostics), vec!["{req}"]);
+        assert_eq!(
+            diagnostics[0].span.unwrap().start,
+            source.find("{req}").unwrap()
+        );
+    }
+
+    #[test]
+    fn tau_substitution_points_at_its_pairs() {
+        let source = "#![start(Main)]\nfn Main() { (tau.a!.nil [done/tau, b/a]) }";
+        let diagnostics = tau_substitutions(&program(source));
+        assert_eq!(covered(source, &diagnostics), vec!["done/tau, b/a"]);
+    }
+
+    #[test]
+    fn identical_summand_points_at_the_repetition() {
+        let source = "#![start(Main)]\nfn Main() { (a!.Main() + a!.Main()) }";
+        let diagnostics = identical_summands(&program(source));
+        assert_eq!(covered(source, &diagnostics), vec!["a!.Main()"]);
+        assert_eq!(
+            diagnostics[0].span.unwrap().start,
+            source.rfind("a!.Main()").unwrap()
+        );
+    }
+
+    #[test]
+    fn unreachable_constant_points_at_its_name() {
+        let source = "#![start(Main)]\nfn Main() { nil }\nfn Unused() { nil }";
+        let diagnostics = unreachable_constants(&program(source));
+        assert_eq!(covered(source, &diagnostics), vec!["Unused"]);
+    }
+
+    #[test]
+    fn shadowed_variable_points_at_the_received_name() {
+        let source = "#![start(main)]\nfn main() { a?(x).b?(x).c!(x).nil }";
+        let program = ProgramVp::try_from(source).unwrap();
+        let diagnostics = shadowed_variables(&program, source);
+        assert_eq!(covered(source, &diagnostics), vec!["x"]);
+        assert_eq!(
+            diagnostics[0].span.unwrap().start,
+            source.find("b?(x)").unwrap() + 3
+        );
+    }
+}
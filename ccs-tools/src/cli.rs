@@ -1,5 +1,13 @@
-use ccs::utils::{print_bisimulation, print_ccs, print_transitions};
+use ccs::check::check;
+use ccs::diagnostic::{Diagnostic, Severity};
+use ccs::error::Error;
+use ccs::game::bisimilar;
+use ccs::process::{Explorer, Process};
+use ccs::utils::{print_bisimulation, print_ccs, print_game, print_transitions};
+use ccs_lint::linter::{Linter, to_json};
+use ccs_lint::rule::Rule;
 
+use crate::game::play_game;
 use crate::renderer::render_lts;
 
 pub enum Arg {
@@ -13,6 +21,13 @@ pub struct Cli {
     bisim: bool,
     lts: bool,
     render: bool,
+    game: Option<(String, String)>,
+    play: bool,
+    prism: Option<String>,
+    ctmc: Option<String>,
+    lint: bool,
+    json: bool,
+    allow: Vec<Rule>,
 }
 impl Cli {
     fn default() -> Self {
@@ -22,6 +37,13 @@ impl Cli {
             bisim: Default::default(),
             lts: Default::default(),
             render: Default::default(),
+            game: Default::default(),
+            play: Default::default(),
+            prism: Default::default(),
+            ctmc: Default::default(),
+            lint: Default::default(),
+            json: Default::default(),
+            allow: Default::default(),
         }
     }
     pub fn parse_args() -> Self {
@@ -41,41 +63,155 @@ impl Cli {
                 "bisim" => self.bisim = true,
                 "lts" => self.lts = true,
                 "render" => self.render = true,
+                "play" => self.play = true,
+                "lint" => self.lint = true,
+                "json" => self.json = true,
                 _ => {}
             },
-            Arg::Value(name, value) => {
-                if name.as_str() == "source" {
-                    self.source = value
+            Arg::Value(name, value) => match name.as_str() {
+                "source" => self.source = value,
+                "prism" => self.prism = Some(value),
+                "ctmc" => self.ctmc = Some(value),
+                "allow" => {
+                    self.allow = value
+                        .split(",")
+                        .map(|rule| rule.trim().parse().unwrap_or_else(|e| panic!("{e}")))
+                        .collect()
                 }
-            }
+                "game" => match value.split(",").collect::<Vec<_>>().as_slice() {
+                    [l, r] => self.game = Some((l.trim().to_string(), r.trim().to_string())),
+                    _ => panic!("Invalid game: {value}, expected game=P,Q"),
+                },
+                _ => {}
+            },
         }
 
         self
     }
     pub async fn exec(self) {
-        if !self.source.ends_with(".ccs") && !self.source.ends_with(".ccsvp") {
-            panic!("Invalid source file extension: {}", self.source);
+        let source = match std::fs::read_to_string(&self.source) {
+            Ok(source) => source,
+            Err(e) => return eprint!("{}", io_error(e).render("", &self.source)),
+        };
+        if self.lint {
+            return self.lint(&source);
         }
-        let source = std::fs::read_to_string(&self.source).unwrap();
-        let ccs = ccs_vp::context::Context::try_from(source.as_str())
-            .map_or_else(
-                |_| ccs::context::Context::try_from(source.as_str()),
-                |ctx| Ok(ctx.to_ccs()),
-            )
-            .unwrap();
-
+        let ccs = match self.load(&source) {
+            Ok(ccs) => ccs,
+            Err(diagnostics) => {
+                for d in diagnostics {
+                    eprint!("{}", d.render(&source, &self.source));
+                }
+                return;
+            }
+        };
+        if let Err(d) = self.run(&ccs).await {
+            eprint!("{}", d.render(&source, &self.source));
+        }
+    }
+    fn lint(&self, source: &str) {
+        let linter = self
+            .allow
+            .iter()
+            .fold(Linter::new(), |l, rule| l.allow(*rule));
+        let lints = if self.source.ends_with(".ccsvp") {
+            let (program, errors) = ccs_vp::ast::Program::parse(source);
+            for e in errors {
+                eprint!("{}", e.diagnostic().render(source, &self.source));
+            }
+            linter.lint_ccs_vp(&program, source)
+        } else {
+            let (program, errors) = ccs::ast::Program::parse(source);
+            for e in errors {
+                eprint!("{}", e.diagnostic().render(source, &self.source));
+            }
+            linter.lint_ccs(&program)
+        };
+        if self.json {
+            println!("{}", to_json(&lints, source, &self.source));
+        } else {
+            for lint in lints {
+                print!("{}", lint.render(source, &self.source));
+            }
+        }
+    }
+    fn load(&self, source: &str) -> Result<ccs::context::Context, Vec<Diagnostic>> {
+        let single = |d: Diagnostic| vec![d];
+        let report = |diagnostics: Vec<Diagnostic>| {
+            if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                return Err(diagnostics);
+            }
+            for d in diagnostics {
+                eprint!("{}", d.render(source, &self.source));
+            }
+            Ok(())
+        };
+        if self.source.ends_with(".ccsvp") {
+            let (program, errors) = ccs_vp::ast::Program::parse(source);
+            if !errors.is_empty() {
+                return Err(errors.iter().map(|e| e.diagnostic()).collect());
+            }
+            report(ccs_vp::check::check(&program, source))?;
+            ccs_vp::context::Context::try_from(program)
+                .and_then(|ctx| ctx.to_ccs())
+                .map_err(|e| single(e.diagnostic()))
+        } else if self.source.ends_with(".ccs") {
+            let (program, errors) = ccs::ast::Program::parse(source);
+            if !errors.is_empty() {
+                return Err(errors.iter().map(|e| e.diagnostic()).collect());
+            }
+            report(check(&program, source))?;
+            ccs::context::Context::try_from(program).map_err(|e| single(e.diagnostic()))
+        } else {
+            let message = format!("invalid source file extension: {}", self.source);
+            Err(single(
+                Diagnostic::error(message, None).with_hint("expected .ccs or .ccsvp"),
+            ))
+        }
+    }
+    async fn run(&self, ccs: &ccs::context::Context) -> Result<(), Diagnostic> {
         if self.ccs {
-            print_ccs(&ccs)
+            print_ccs(ccs)
         }
-        let lts = ccs.to_lts().flatten();
+        let lts = ccs.to_lts().map_err(|e| e.diagnostic())?.flatten();
         if self.lts {
             print_transitions(&lts)
         }
         if self.bisim {
             print_bisimulation(&lts.bisimilarity(&lts))
         }
+        if let Some((l, r)) = &self.game {
+            for name in [l, r] {
+                if ccs.get_process(name).is_none() {
+                    return Err(Error::UndefinedConstant(name.clone()).diagnostic());
+                }
+            }
+            let t = Explorer::new(ccs, Process::constant(l));
+            let u = Explorer::new(ccs, Process::constant(r));
+            match bisimilar(&t, &u) {
+                Ok(()) => println!("{l} and {r} are bisimilar"),
+                Err(cex) if self.play => play_game(&t, &u, &cex),
+                Err(cex) => print_game(&t, &u, &cex),
+            }
+        }
+        if let Some(prefix) = &self.prism {
+            let pts = ccs.to_pts().map_err(|e| e.diagnostic())?;
+            std::fs::write(format!("{prefix}.tra"), pts.to_prism_tra()).map_err(io_error)?;
+            std::fs::write(format!("{prefix}.sta"), pts.to_prism_sta()).map_err(io_error)?;
+            std::fs::write(format!("{prefix}.lab"), pts.to_prism_lab()).map_err(io_error)?;
+        }
+        if let Some(prefix) = &self.ctmc {
+            let ctmc = ccs.to_ctmc().map_err(|e| e.diagnostic())?;
+            std::fs::write(format!("{prefix}.tra"), ctmc.to_prism_tra()).map_err(io_error)?;
+            std::fs::write(format!("{prefix}.mtx"), ctmc.to_matrix_market()).map_err(io_error)?;
+        }
         if self.render {
             render_lts(&lts).await;
         }
+        Ok(())
     }
 }
+
+fn io_error(e: std::io::Error) -> Diagnostic {
+    Diagnostic::error(e.to_string(), None)
+}
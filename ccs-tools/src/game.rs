@@ -0,0 +1,46 @@
+use std::io::{BufRead, Write};
+
+use ccs::game::{Counterexample, Move, answers};
+use ccs::process::{Channel, Explorer, Process};
+
+pub fn play_game(t: &Explorer, u: &Explorer, cex: &Counterexample<Process, Process, Channel>) {
+    let mut pair = cex.init().clone();
+    let mut stdin = std::io::stdin().lock();
+    loop {
+        let m = cex.attack(pair.0.clone(), pair.1.clone()).unwrap();
+        let (this, other) = match m {
+            Move::Left(a, p2) => {
+                println!("Attacker: {} --{a}--> {p2}", pair.0);
+                (&pair.1, a)
+            }
+            Move::Right(b, q2) => {
+                println!("Attacker: {} --{b}--> {q2}", pair.1);
+                (&pair.0, b)
+            }
+        };
+        let answers = answers(t, u, &pair, m);
+        if answers.is_empty() {
+            println!("Defender: {this} cannot do {other}, the attacker wins");
+            return;
+        }
+        for (i, (p2, q2)) in answers.iter().enumerate() {
+            match m {
+                Move::Left(..) => println!("  [{i}] {this} --{other}--> {q2}"),
+                Move::Right(..) => println!("  [{i}] {this} --{other}--> {p2}"),
+            }
+        }
+        let choice = loop {
+            print!("Defender> ");
+            std::io::stdout().flush().unwrap();
+            let mut line = String::new();
+            if stdin.read_line(&mut line).unwrap() == 0 {
+                return;
+            }
+            match line.trim().parse::<usize>() {
+                Ok(i) if i < answers.len() => break i,
+                _ => println!("Pick a number between 0 and {}", answers.len() - 1),
+            }
+        };
+        pair = answers[choice].clone();
+    }
+}
@@ -1,4 +1,5 @@
 mod cli;
+mod game;
 mod renderer;
 
 #[macroquad::main("LtsRenderer")]
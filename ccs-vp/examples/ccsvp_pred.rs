@@ -4,5 +4,5 @@ use ccs_vp::context::Context;
 fn main() {
     let source = include_str!("ccsvp_pred.ccsvp");
     let ctx = Context::try_from(source).unwrap();
-    run_example(ctx.to_ccs());
+    run_example(ctx.to_ccs().unwrap());
 }
@@ -1,18 +1,40 @@
 use std::fmt::{Debug, Display};
 
+use ccs::diagnostic::Spanned;
 use itertools::Itertools;
-use lalrpop_util::lexer::Token;
-use lalrpop_util::{lalrpop_mod, ParseError};
+use lalrpop_util::lalrpop_mod;
 
 use super::process::Process;
+use crate::error::{self, Error};
 
-lalrpop_mod!(pub ccs_vp);
+lalrpop_mod!(#[allow(clippy::ptr_arg)] pub ccs_vp);
 
-pub struct Program(pub Vec<Statement>);
-impl<'a> TryFrom<&'a str> for Program {
-    type Error = ParseError<usize, Token<'a>, &'static str>;
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        ccs_vp::ProgramNodeParser::new().parse(value)
+pub struct Program(pub Vec<Spanned<Statement>>);
+impl Program {
+    pub fn parse(source: &str) -> (Self, Vec<Error>) {
+        let mut recovered = vec![];
+        let result = ccs_vp::ProgramNodeParser::new().parse(&mut recovered, source);
+        let mut errors = recovered
+            .into_iter()
+            .map(|e| Error::from(e.error))
+            .collect::<Vec<_>>();
+        match result {
+            Ok(program) => (program, errors),
+            Err(e) => {
+                errors.push(Error::from(e));
+                (Self(vec![]), errors)
+            }
+        }
+    }
+}
+impl TryFrom<&str> for Program {
+    type Error = Error;
+    fn try_from(value: &str) -> error::Result<Self> {
+        let (program, errors) = Self::parse(value);
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(program),
+        }
     }
 }
 impl Display for Program {
@@ -31,10 +53,12 @@ pub enum Command {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Statement {
-    DefConstant(String, (Vec<String>, Process)),
+    DefConstant(Spanned<String>, (Vec<String>, Process)),
     DefEnum(String, Vec<(String, Vec<String>)>),
     DefAlias(String, String),
+    DefChannel(String, Option<u32>),
     Exec(Command),
 }
 impl Display for Statement {
@@ -51,6 +75,8 @@ impl Display for Statement {
                 write!(f, "enum {name} {{ {tags} }}")
             }
             Statement::DefAlias(alias, ty) => write!(f, "type {alias} = {ty};"),
+            Statement::DefChannel(name, Some(n)) => write!(f, "chan {name}: buffered({n});"),
+            Statement::DefChannel(name, None) => write!(f, "chan {name}: async;"),
             Statement::Exec(c) => write!(f, "#![{c:?}]"),
         }
     }
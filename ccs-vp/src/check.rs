@@ -0,0 +1,190 @@
+use std::collections::{HashMap, HashSet};
+
+use ccs::diagnostic::{line_col, Diagnostic, Span};
+use ccs::error::Error as ErrorCcs;
+use itertools::Itertools;
+
+use crate::ast::{Command, Program, Statement};
+use crate::error::Error;
+use crate::process::{Channel, Process};
+
+pub fn check(program: &Program, source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut defined: HashMap<&str, (Span, usize)> = HashMap::new();
+    let mut constants = HashMap::new();
+    let mut main = None;
+    for stmt in &program.0 {
+        match &stmt.node {
+            Statement::DefConstant(name, (params, p)) => {
+                if let Some((first, _)) = defined.get(name.node.as_str()) {
+                    let (line, _) = line_col(source, first.start);
+                    let e = ErrorCcs::DuplicateConstant(name.node.clone()).at(Some(name.span));
+                    diagnostics.push(
+                        e.diagnostic()
+                            .with_hint(format!("first defined on line {line}")),
+                    );
+                } else {
+                    defined.insert(name.node.as_str(), (name.span, params.len()));
+                    constants.insert(name.node.clone(), p);
+                }
+            }
+            Statement::Exec(Command::SetMain(name)) => main = Some((name, stmt.span)),
+            _ => {}
+        }
+    }
+
+    match main {
+        None => diagnostics.push(ErrorCcs::MissingMain.diagnostic()),
+        Some((name, span)) if !defined.contains_key(name.as_str()) => {
+            let e = Error::UndefinedMain(name.clone()).at(Some(span));
+            diagnostics.push(e.diagnostic());
+        }
+        Some(_) => {}
+    }
+
+    let table = channel_table(&constants);
+    for stmt in &program.0 {
+        let Statement::DefConstant(_, (_, p)) = &stmt.node else {
+            continue;
+        };
+        for (x, arity, span) in calls(p) {
+            let e = match defined.get(x.as_str()) {
+                None => Error::UndefinedConstant(x.clone()),
+                Some((_, expected)) if *expected != arity => {
+                    Error::ArityMismatch(x.clone(), *expected, arity)
+                }
+                Some(_) => continue,
+            };
+            diagnostics.push(e.at(span).diagnostic());
+        }
+        check_scopes(p, &table, &mut diagnostics);
+    }
+    diagnostics.sort_by_key(|d| d.span.map(|s| s.start));
+    diagnostics
+}
+
+// every call with its number of arguments, in source order
+fn calls(p: &Process) -> Vec<(String, usize, Option<Span>)> {
+    match p {
+        Process::Constant(x, vals, loc) => vec![(x.clone(), vals.len(), loc.span())],
+        _ => p.children().into_iter().flat_map(calls).collect(),
+    }
+}
+
+fn check_scopes(
+    p: &Process,
+    table: &HashMap<String, HashSet<String>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let unused = |q: &Process, chans: Vec<&String>| {
+        let used = channels(q, table);
+        chans
+            .into_iter()
+            .filter(|x| !used.contains(*x))
+            .cloned()
+            .collect_vec()
+    };
+    match p {
+        Process::Restriction(q, chans, loc) => {
+            for x in unused(q, chans.iter().collect()) {
+                let message = format!("restricted channel \"{x}\" never occurs in its scope");
+                diagnostics.push(Diagnostic::warning(message, loc.span()));
+            }
+        }
+        Process::Substitution(q, subs) => {
+            for x in unused(q, subs.pairs().iter().map(|(_, old)| old).collect()) {
+                let message = format!("substituted channel \"{x}\" never occurs in its scope");
+                diagnostics.push(Diagnostic::warning(message, subs.span()));
+            }
+        }
+        _ => {}
+    }
+    for q in p.children() {
+        check_scopes(q, table, diagnostics);
+    }
+}
+
+fn channel_table(constants: &HashMap<String, &Process>) -> HashMap<String, HashSet<String>> {
+    let mut table = HashMap::<String, HashSet<String>>::new();
+    loop {
+        let mut changed = false;
+        for (name, p) in constants {
+            let chans = channels(p, &table);
+            let entry = table.entry(name.clone()).or_default();
+            if chans.len() > entry.len() {
+                *entry = chans;
+                changed = true;
+            }
+        }
+        if !changed {
+            return table;
+        }
+    }
+}
+
+fn channels(p: &Process, table: &HashMap<String, HashSet<String>>) -> HashSet<String> {
+    match p {
+        Process::Constant(x, ..) => table.get(x).cloned().unwrap_or_default(),
+        Process::Action(Channel::Send(x, _) | Channel::Recv(x, _), q, _) => {
+            let mut chans = channels(q, table);
+            chans.insert(x.clone());
+            chans
+        }
+        Process::Restriction(q, names, _) | Process::Hiding(q, names) => {
+            let mut chans = channels(q, table);
+            chans.retain(|x| !names.contains(x));
+            chans
+        }
+        Process::Substitution(q, subs) => channels(q, table)
+            .into_iter()
+            .map(|x| subs.replace(x))
+            .collect(),
+        _ => p
+            .children()
+            .into_iter()
+            .flat_map(|q| channels(q, table))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each diagnostic's message, start offset and the source text it covers.
+    fn located(source: &str) -> Vec<(String, usize, &str)> {
+        let program = Program::try_from(source).unwrap();
+        check(&program, source)
+            .into_iter()
+            .map(|d| {
+                let span = d.span.expect("diagnostic without a location");
+                (d.message, span.start, &source[span.start..span.end])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unused_restriction_points_at_its_channel_set() {
+        let source = "#![start(main)]\nfn main() { (b!.nil | (a!.nil \\ {b})) }";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1, source.find("{b}").unwrap());
+        assert_eq!(diagnostics[0].2, "{b}");
+    }
+
+    #[test]
+    fn unused_restriction_on_a_definition_points_at_its_channel_set() {
+        let source = "#![start(main)]\nfn main() { b!.a!.nil }\nfn p() { a!.nil } \\ {b}";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1, source.rfind("{b}").unwrap());
+    }
+
+    #[test]
+    fn arity_mismatch_points_at_the_call() {
+        let source = "#![start(main)]\nfn main() { a!.p(1, 2) }\nfn p(x) { a!(x).nil }";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].2, "p(1, 2)");
+    }
+}
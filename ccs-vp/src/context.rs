@@ -1,12 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
 use ccs::context::Context as ContextCcs;
-use lalrpop_util::lexer::Token;
-use lalrpop_util::ParseError;
+use ccs::diagnostic::Span;
+use itertools::Itertools;
 
 use super::ast::{Command, Program, Statement};
-use super::process::Process;
+use super::process::{encode_action, Process};
 use super::values::{AExpr, BExpr, Value};
+use crate::error::{Error, Result};
 use crate::utils::permute;
 use crate::values::Enum;
 
@@ -16,6 +17,7 @@ pub struct Context {
     constants: HashMap<String, (Vec<String>, Process)>,
     enums: HashMap<String, Vec<(String, Vec<String>)>>,
     aliases: HashMap<String, String>,
+    buffers: HashMap<String, Option<u32>>,
     int_bounds: (u32, u32),
     cached_values: HashMap<String, Vec<Value>>,
 }
@@ -31,13 +33,13 @@ impl Context {
             .chain(self.aliases.keys().cloned())
             .collect()
     }
-    pub fn type_of<'a>(&self, v: &'a Value) -> &'a str {
+    pub fn type_of<'a>(&self, v: &'a Value) -> Result<&'a str> {
         match v {
-            Value::AExpr(_) => Self::INT_TY,
-            Value::BExpr(_) => Self::BOOL_TY,
-            Value::Enum(Enum::Var(_)) => panic!(),
-            Value::Enum(Enum::Lit(ty, ..)) => ty.as_str(),
-            Value::Any(_) => Self::ANY_TY,
+            Value::AExpr(_) => Ok(Self::INT_TY),
+            Value::BExpr(_) => Ok(Self::BOOL_TY),
+            Value::Enum(Enum::Var(x)) => Err(Error::FreeVariable(x.clone())),
+            Value::Enum(Enum::Lit(ty, ..)) => Ok(ty.as_str()),
+            Value::Any(_) => Ok(Self::ANY_TY),
         }
     }
 
@@ -48,27 +50,38 @@ impl Context {
         &self.constants
     }
 
-    pub fn to_ccs(&self) -> ContextCcs {
-        self.constants
-            .get(&self.main)
-            .unwrap_or_else(|| panic!("Main process \"{}\" not found", self.main));
+    pub fn to_ccs(&self) -> Result<ContextCcs> {
+        if !self.constants.contains_key(&self.main) {
+            return Err(Error::UndefinedMain(self.main.clone()));
+        }
 
         let mut ccs = ContextCcs::default();
         ccs.set_main(self.main.clone());
-        Process::constant(&self.main, vec![]).to_ccs(self, &mut ccs, &mut HashSet::new());
-        ccs
-    }
-    pub fn bind_enum(&mut self, ty: String, tags: Vec<(String, Vec<String>)>) {
-        assert_ne!(ty, Self::ANY_TY);
-        assert!(tags
-            .iter()
-            .flat_map(|(_, fields)| fields)
-            .all(|field| *field != ty && field != Self::ANY_TY
-                || field == Self::INT_TY
-                || field == Self::BOOL_TY
-                || self.enums.contains_key(field)
-                || self.aliases.contains_key(field)));
+        let values = self.values()?;
+        for (chan, capacity) in &self.buffers {
+            let names = [chan.clone()]
+                .into_iter()
+                .chain(values.iter().map(|v| encode_action(chan.clone(), v)))
+                .collect();
+            ccs.bind_buffer(chan.clone(), *capacity, names);
+        }
+        Process::constant(&self.main, vec![]).to_ccs(self, &mut ccs, &mut HashSet::new())?;
+        Ok(ccs)
+    }
+    pub fn bind_enum(&mut self, ty: String, tags: Vec<(String, Vec<String>)>) -> Result<()> {
+        let valid = ty != Self::ANY_TY
+            && tags.iter().flat_map(|(_, fields)| fields).all(|field| {
+                *field != ty && field != Self::ANY_TY
+                    || field == Self::INT_TY
+                    || field == Self::BOOL_TY
+                    || self.enums.contains_key(field)
+                    || self.aliases.contains_key(field)
+            });
+        if !valid {
+            return Err(Error::InvalidType(ty));
+        }
         self.enums.insert(ty, tags);
+        Ok(())
     }
     pub fn bind_process(&mut self, name: String, p: (Vec<String>, Process)) {
         self.constants.insert(name, p);
@@ -76,12 +89,18 @@ impl Context {
     pub fn bind_alias(&mut self, alias: String, ty: String) {
         self.aliases.insert(alias, ty);
     }
+    pub fn bind_buffer(&mut self, chan: String, capacity: Option<u32>) {
+        self.buffers.insert(chan, capacity);
+    }
     pub fn get_process(&self, name: &str) -> Option<&(Vec<String>, Process)> {
         self.constants.get(name)
     }
-    pub fn set_bounds(&mut self, (min, max): (u32, u32)) {
-        assert!(min < max);
+    pub fn set_bounds(&mut self, (min, max): (u32, u32)) -> Result<()> {
+        if min >= max {
+            return Err(Error::InvalidBounds(min, max));
+        }
         self.int_bounds = (min, max);
+        Ok(())
     }
     pub fn set_main(&mut self, main: String) {
         self.main = main;
@@ -89,24 +108,27 @@ impl Context {
     pub fn bounds(&self) -> (u32, u32) {
         self.int_bounds
     }
-    pub fn values(&self) -> Vec<Value> {
-        assert!(!self.cached_values.is_empty());
-        self.cached_values.values().flatten().cloned().collect()
+    pub fn values(&self) -> Result<Vec<Value>> {
+        self.types()
+            .iter()
+            .map(|ty| self.values_of(ty))
+            .flatten_ok()
+            .collect()
     }
-    pub fn values_of(&self, ty: &str) -> Vec<Value> {
+    pub fn values_of(&self, ty: &str) -> Result<Vec<Value>> {
         if let Some(vals) = self.cached_values.get(ty) {
-            return vals.clone();
+            return Ok(vals.clone());
         }
         if let Some(ty) = self.aliases.get(ty) {
             return self.values_of(ty);
         }
         if ty == Self::BOOL_TY {
-            return [true, false].map(|v| Value::BExpr(BExpr::Lit(v))).to_vec();
+            return Ok([true, false].map(|v| Value::BExpr(BExpr::Lit(v))).to_vec());
         }
         if ty == Self::INT_TY {
-            return (self.int_bounds.0..self.int_bounds.1)
+            return Ok((self.int_bounds.0..self.int_bounds.1)
                 .map(|v| Value::AExpr(AExpr::Lit(v)))
-                .collect();
+                .collect());
         }
         if let Some(tags) = self.enums.get(ty) {
             let mut values = vec![];
@@ -115,44 +137,65 @@ impl Context {
                     values.push(Value::Enum(Enum::Lit(ty.to_string(), tag.clone(), vec![])));
                     continue;
                 }
-                let tag_field_vals = fields.iter().map(|f| self.values_of(f)).collect();
+                let tag_field_vals = fields
+                    .iter()
+                    .map(|f| self.values_of(f))
+                    .collect::<Result<_>>()?;
                 for perm in permute(tag_field_vals) {
                     values.push(Value::Enum(Enum::Lit(ty.to_string(), tag.clone(), perm)));
                 }
             }
-            return values;
+            return Ok(values);
         }
-        panic!("{ty} is not a valid type");
+        Err(Error::UndefinedType(ty.to_string()))
     }
-    fn gen_values(&mut self) {
+    // spans are where each type is declared, to locate the one that fails
+    fn gen_values(&mut self, spans: &HashMap<String, Span>) -> Result<()> {
         self.cached_values.clear();
         for ty in self.types() {
-            self.cached_values
-                .insert(ty.to_string(), self.values_of(&ty));
+            let values = self
+                .values_of(&ty)
+                .map_err(|e| e.at(spans.get(&ty).copied()))?;
+            self.cached_values.insert(ty.to_string(), values);
         }
+        Ok(())
     }
 }
-impl From<Program> for Context {
-    fn from(value: Program) -> Self {
+impl TryFrom<Program> for Context {
+    type Error = Error;
+    fn try_from(value: Program) -> Result<Self> {
         let mut ctx = Self::default();
+        let mut types = HashMap::new();
         for stmt in value.0 {
-            match stmt {
-                Statement::DefConstant(name, def) => ctx.bind_process(name, def.clone()),
-                Statement::DefEnum(name, tags) => ctx.bind_enum(name, tags.clone()),
-                Statement::DefAlias(alias, ty) => ctx.bind_alias(alias, ty),
+            let at = |e: Error| e.at(Some(stmt.span));
+            match stmt.node {
+                Statement::DefConstant(name, _) if ctx.constants.contains_key(&name.node) => {
+                    let e = ccs::error::Error::DuplicateConstant(name.node);
+                    return Err(Error::Ccs(e.at(Some(name.span))));
+                }
+                Statement::DefConstant(name, def) => ctx.bind_process(name.node, def),
+                Statement::DefEnum(name, tags) => {
+                    types.insert(name.clone(), stmt.span);
+                    ctx.bind_enum(name, tags).map_err(at)?
+                }
+                Statement::DefAlias(alias, ty) => {
+                    types.insert(alias.clone(), stmt.span);
+                    ctx.bind_alias(alias, ty)
+                }
+                Statement::DefChannel(name, capacity) => ctx.bind_buffer(name, capacity),
                 Statement::Exec(cmd) => match cmd {
-                    Command::SetBounds(min, max) => ctx.set_bounds((min, max)),
+                    Command::SetBounds(min, max) => ctx.set_bounds((min, max)).map_err(at)?,
                     Command::SetMain(main) => ctx.set_main(main),
                 },
             }
         }
-        ctx.gen_values();
-        ctx
+        ctx.gen_values(&types)?;
+        Ok(ctx)
     }
 }
-impl<'a> TryFrom<&'a str> for Context {
-    type Error = ParseError<usize, Token<'a>, &'static str>;
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        Ok(Self::from(Program::try_from(value)?))
+impl TryFrom<&str> for Context {
+    type Error = Error;
+    fn try_from(value: &str) -> Result<Self> {
+        Self::try_from(Program::try_from(value)?)
     }
 }
@@ -0,0 +1,114 @@
+use std::fmt::Display;
+
+use ccs::diagnostic::{Diagnostic, Span};
+use lalrpop_util::lexer::Token;
+use lalrpop_util::ParseError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Ccs(ccs::error::Error),
+    Parse(ParseError<usize, String, &'static str>),
+    UndefinedConstant(String),
+    UndefinedMain(String),
+    UndefinedType(String),
+    UndefinedTag(String, String),
+    InvalidType(String),
+    InvalidBounds(u32, u32),
+    FreeVariable(String),
+    OutOfBounds(String, (u32, u32)),
+    DivisionByZero(String),
+    ArityMismatch(String, usize, usize),
+    TypeMismatch(String, String),
+    InvalidArguments(String, Vec<String>),
+    Located(Box<Error>, Span),
+}
+impl Error {
+    // errors keep the innermost position they are given
+    pub fn at(self, span: Option<Span>) -> Self {
+        match (self, span) {
+            (e, _) if e.span().is_some() => e,
+            (e, None) => e,
+            (e, Some(span)) => Error::Located(Box::new(e), span),
+        }
+    }
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::Ccs(e) => e.span(),
+            Error::Located(_, span) => Some(*span),
+            _ => None,
+        }
+    }
+    pub fn diagnostic(&self) -> Diagnostic {
+        match self {
+            Error::Ccs(e) => e.diagnostic(),
+            Error::Parse(e) => Diagnostic::from_parse_error(e),
+            Error::Located(e, span) => Diagnostic {
+                span: Some(*span),
+                ..e.diagnostic()
+            },
+            Error::UndefinedConstant(_)
+            | Error::UndefinedMain(_)
+            | Error::UndefinedType(_)
+            | Error::UndefinedTag(..)
+            | Error::InvalidType(_)
+            | Error::InvalidBounds(..)
+            | Error::FreeVariable(_)
+            | Error::OutOfBounds(..)
+            | Error::DivisionByZero(_)
+            | Error::ArityMismatch(..)
+            | Error::TypeMismatch(..)
+            | Error::InvalidArguments(..) => Diagnostic::error(self.to_string(), None),
+        }
+    }
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Ccs(e) => write!(f, "{e}"),
+            Error::Parse(e) => write!(f, "{e}"),
+            Error::UndefinedConstant(name) => write!(f, "constant \"{name}\" is not defined"),
+            Error::UndefinedMain(name) => write!(f, "main process \"{name}\" is not defined"),
+            Error::UndefinedType(ty) => write!(f, "type \"{ty}\" is not defined"),
+            Error::UndefinedTag(ty, tag) => write!(f, "\"{ty}\" has no tag \"{tag}\""),
+            Error::InvalidType(ty) => write!(f, "\"{ty}\" is not a valid type declaration"),
+            Error::InvalidBounds(min, max) => write!(f, "invalid bounds {min}..{max}"),
+            Error::FreeVariable(x) => write!(f, "free variable \"{x}\""),
+            Error::OutOfBounds(e, (min, max)) => {
+                write!(f, "{e} is out of the bounds {min}..{max}")
+            }
+            Error::DivisionByZero(e) => write!(f, "division by zero in {e}"),
+            Error::ArityMismatch(name, expected, found) => {
+                write!(f, "\"{name}\" expects {expected} values, found {found}")
+            }
+            Error::TypeMismatch(expected, found) => {
+                write!(
+                    f,
+                    "expected a value of type \"{expected}\", found \"{found}\""
+                )
+            }
+            Error::InvalidArguments(name, vals) => {
+                write!(f, "invalid arguments for \"{name}\": {}", vals.join(", "))
+            }
+            Error::Located(e, _) => write!(f, "{e}"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<ccs::error::Error> for Error {
+    fn from(e: ccs::error::Error) -> Self {
+        Error::Ccs(e)
+    }
+}
+impl<'a> From<ParseError<usize, Token<'a>, Error>> for Error {
+    fn from(e: ParseError<usize, Token<'a>, Error>) -> Self {
+        match e {
+            ParseError::User { error } => error,
+            e => Error::Parse(
+                e.map_token(|t| t.1.to_string())
+                    .map_error(|_| unreachable!("user errors are returned as they are")),
+            ),
+        }
+    }
+}
@@ -1,5 +1,7 @@
 pub mod ast;
+pub mod check;
 pub mod context;
+pub mod error;
 pub mod process;
 pub mod utils;
 pub mod values;
@@ -2,21 +2,25 @@ use std::collections::HashSet;
 use std::fmt::Display;
 
 use ccs::context::Context as ContextCcs;
+use ccs::diagnostic::{Loc, Span};
 use ccs::process::{Channel as ChannelCcs, Process as ProcessCcs, Substitution};
 use itertools::Itertools;
 
 use super::context::Context;
 use super::values::{BExpr, Value};
+use crate::error::{Error, Result};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Process {
-    Constant(String, Vec<Value>),
-    Action(Channel, Box<Process>),
+    Constant(String, Vec<Value>, Loc),
+    Action(Channel, Box<Process>, Loc),
     Sum(Vec<Process>),
     Par(Box<Process>, Box<Process>),
-    Restriction(Box<Process>, Vec<String>),
+    Disrupt(Box<Process>, Box<Process>),
+    Restriction(Box<Process>, Vec<String>, Loc),
+    Hiding(Box<Process>, Vec<String>),
     Substitution(Box<Process>, Substitution),
-    IfThen(BExpr, Box<Process>),
+    IfThen(BExpr, Box<Process>, Loc),
 }
 
 impl Process {
@@ -25,80 +29,112 @@ impl Process {
         ctx: &Context,
         ccs: &mut ContextCcs,
         seen: &mut HashSet<String>,
-    ) -> ProcessCcs {
-        match self {
-            Process::Constant(name, vals) => {
-                let vals = vals.iter().map(|v| v.eval(ctx)).collect_vec();
+    ) -> Result<ProcessCcs> {
+        Ok(match self {
+            Process::Constant(name, vals, loc) => {
+                let at = |e: Error| e.at(loc.span());
+                let vals = vals
+                    .iter()
+                    .map(|v| v.eval(ctx))
+                    .collect::<Result<Vec<_>>>()
+                    .map_err(at)?;
                 let encoded = encode_constant(name.to_string(), &vals);
                 if seen.contains(&encoded) {
-                    return ProcessCcs::constant(encoded);
+                    return Ok(ProcessCcs::constant(encoded));
                 }
                 let (vars, mut body) = ctx
                     .get_process(&name)
-                    .unwrap_or_else(|| panic!("[error] constant \"{name}\" not found"))
+                    .ok_or_else(|| at(Error::UndefinedConstant(name.clone())))?
                     .clone();
+                if vars.len() != vals.len() {
+                    return Err(at(Error::ArityMismatch(name, vars.len(), vals.len())));
+                }
                 if !vars
                     .iter()
                     .zip(vals.iter())
                     .all(|(var, val)| body.try_replace(var, val))
                 {
-                    panic!("[error] failed to replace {vars:?} with {vals:?}");
+                    let vals = vals.iter().map(Value::to_string).collect();
+                    return Err(at(Error::InvalidArguments(name, vals)));
                 }
                 seen.insert(encoded.clone());
-                let body = body.clone().to_ccs(ctx, ccs, seen).flatten();
+                let body = body.clone().to_ccs(ctx, ccs, seen)?.flatten();
                 ccs.bind_process(encoded.clone(), body);
                 seen.remove(&encoded);
                 ProcessCcs::constant(encoded)
             }
-            Process::Action(ch, p) => match ch {
-                Channel::Tau => ProcessCcs::action(ChannelCcs::Tau, p.to_ccs(ctx, ccs, seen)),
+            Process::Action(ch, p, loc) => match ch {
+                Channel::Tau => ProcessCcs::action(ChannelCcs::Tau, p.to_ccs(ctx, ccs, seen)?),
                 Channel::Recv(name, None) => {
-                    ProcessCcs::action(ChannelCcs::Recv(name), p.to_ccs(ctx, ccs, seen))
+                    ProcessCcs::action(ChannelCcs::Recv(name), p.to_ccs(ctx, ccs, seen)?)
                 }
                 Channel::Send(name, None) => {
-                    ProcessCcs::action(ChannelCcs::Send(name), p.to_ccs(ctx, ccs, seen))
+                    ProcessCcs::action(ChannelCcs::Send(name), p.to_ccs(ctx, ccs, seen)?)
                 }
                 Channel::Send(name, Some(e)) => ProcessCcs::action(
-                    ChannelCcs::Send(encode_action(name, &e.eval(ctx))),
-                    p.to_ccs(ctx, ccs, seen),
+                    ChannelCcs::Send(encode_action(
+                        name,
+                        &e.eval(ctx).map_err(|e| e.at(loc.span()))?,
+                    )),
+                    p.to_ccs(ctx, ccs, seen)?,
                 ),
                 Channel::Recv(name, Some(var)) => {
                     let mut possibles = vec![];
-                    for val in ctx.values() {
+                    for val in ctx.values()? {
                         let mut p = p.clone();
                         if p.try_replace(&var, &val) {
                             possibles.push(ProcessCcs::action(
-                                ChannelCcs::Recv(encode_action(name.clone(), &val.eval(ctx))),
-                                p.to_ccs(ctx, ccs, seen),
+                                ChannelCcs::Recv(encode_action(
+                                    name.clone(),
+                                    &val.eval(ctx).map_err(|e| e.at(loc.span()))?,
+                                )),
+                                p.to_ccs(ctx, ccs, seen)?,
                             ));
                         }
                     }
                     ProcessCcs::sum(possibles)
                 }
             },
-            Process::Sum(sum) => {
-                ProcessCcs::sum(sum.into_iter().map(|p| p.to_ccs(ctx, ccs, seen)).collect())
-            }
+            Process::Sum(sum) => ProcessCcs::sum(
+                sum.into_iter()
+                    .map(|p| p.to_ccs(ctx, ccs, seen))
+                    .collect::<Result<_>>()?,
+            ),
             Process::Par(p, q) => {
-                ProcessCcs::par(p.to_ccs(ctx, ccs, seen), q.to_ccs(ctx, ccs, seen))
+                ProcessCcs::par(p.to_ccs(ctx, ccs, seen)?, q.to_ccs(ctx, ccs, seen)?)
+            }
+            Process::Disrupt(p, q) => {
+                ProcessCcs::disrupt(p.to_ccs(ctx, ccs, seen)?, q.to_ccs(ctx, ccs, seen)?)
             }
-            Process::IfThen(b, p) => {
-                if b.eval(ctx) {
-                    p.to_ccs(ctx, ccs, seen)
+            Process::IfThen(b, p, loc) => {
+                if b.eval(ctx).map_err(|e| e.at(loc.span()))? {
+                    p.to_ccs(ctx, ccs, seen)?
                 } else {
                     ProcessCcs::nil()
                 }
             }
-            Process::Restriction(p, chans) => {
-                let values = ctx.values();
+            Process::Restriction(p, chans, _) => {
+                let values = ctx.values()?;
                 let chans = chans
                     .iter()
                     .flat_map(|ch| values.iter().map(|v| encode_action(ch.clone(), v)))
                     .collect();
-                ProcessCcs::restriction(p.to_ccs(ctx, ccs, seen), chans)
+                ProcessCcs::restriction(p.to_ccs(ctx, ccs, seen)?, chans)
+            }
+            Process::Hiding(p, chans) => {
+                let values = ctx.values()?;
+                let chans = chans
+                    .iter()
+                    .flat_map(|ch| {
+                        [ch.clone()]
+                            .into_iter()
+                            .chain(values.iter().map(|v| encode_action(ch.clone(), v)))
+                    })
+                    .collect();
+                ProcessCcs::hiding(p.to_ccs(ctx, ccs, seen)?, chans)
             }
             Process::Substitution(p, subs) => {
-                let values = ctx.values();
+                let values = ctx.values()?;
                 let chans = subs
                     .pairs()
                     .iter()
@@ -108,22 +144,25 @@ impl Process {
                             .map(|v| (encode_action(new.clone(), v), encode_action(old.clone(), v)))
                     })
                     .collect();
-                ProcessCcs::substitution(p.to_ccs(ctx, ccs, seen), Substitution::new(chans))
+                ProcessCcs::substitution(p.to_ccs(ctx, ccs, seen)?, Substitution::new(chans))
             }
-        }
+        })
     }
     fn try_replace(&mut self, var: &str, val: &Value) -> bool {
         match self {
-            Process::Constant(_, vals) => vals.iter_mut().all(|v| v.try_replace(var, val)),
+            Process::Constant(_, vals, _) => vals.iter_mut().all(|v| v.try_replace(var, val)),
             Process::Sum(vec) => vec.iter_mut().all(|p| p.try_replace(var, val)),
-            Process::Action(m, p) => match m {
+            Process::Action(m, p, _) => match m {
                 Channel::Recv(_, Some(x)) if x == var => true,
                 Channel::Send(_, Some(e)) => e.try_replace(var, val) && p.try_replace(var, val),
                 _ => p.try_replace(var, val),
             },
-            Process::Par(p, q) => p.try_replace(var, val) && q.try_replace(var, val),
-            Process::IfThen(b, p) => b.try_replace(var, val) && p.try_replace(var, val),
-            Process::Restriction(p, _) => p.try_replace(var, val),
+            Process::Par(p, q) | Process::Disrupt(p, q) => {
+                p.try_replace(var, val) && q.try_replace(var, val)
+            }
+            Process::IfThen(b, p, _) => b.try_replace(var, val) && p.try_replace(var, val),
+            Process::Restriction(p, _, _) => p.try_replace(var, val),
+            Process::Hiding(p, _) => p.try_replace(var, val),
             Process::Substitution(p, _) => p.try_replace(var, val),
         }
     }
@@ -132,10 +171,10 @@ impl Process {
         Process::sum(vec![])
     }
     pub fn constant(s: impl Into<String>, vals: Vec<Value>) -> Self {
-        Process::Constant(s.into(), vals)
+        Process::Constant(s.into(), vals, Loc::default())
     }
     pub fn action(l: Channel, p: Self) -> Self {
-        Process::Action(l, Box::new(p))
+        Process::Action(l, Box::new(p), Loc::default())
     }
     pub fn sum(procs: Vec<Self>) -> Self {
         Process::Sum(procs)
@@ -143,33 +182,75 @@ impl Process {
     pub fn par(p: Self, q: Self) -> Self {
         Process::Par(Box::new(p), Box::new(q))
     }
+    pub fn disrupt(p: Self, q: Self) -> Self {
+        Process::Disrupt(Box::new(p), Box::new(q))
+    }
     pub fn restriction(p: Self, chans: Vec<String>) -> Self {
-        Process::Restriction(Box::new(p), chans)
+        Process::Restriction(Box::new(p), chans, Loc::default())
+    }
+    pub fn hiding(p: Self, chans: Vec<String>) -> Self {
+        Process::Hiding(Box::new(p), chans)
     }
     pub fn substitution(p: Self, subs: Substitution) -> Self {
         Process::Substitution(Box::new(p), subs)
     }
     pub fn if_then(b: BExpr, p: Self) -> Self {
-        Process::IfThen(b, Box::new(p))
+        Process::IfThen(b, Box::new(p), Loc::default())
     }
     pub fn if_then_else(b: BExpr, p: Self, q: Self) -> Self {
+        Process::if_then_else_at(b, p, q, Loc::default())
+    }
+    pub(crate) fn if_then_else_at(b: BExpr, p: Self, q: Self, loc: Loc) -> Self {
         Process::sum(vec![
-            Process::IfThen(b.clone(), Box::new(p)),
-            Process::IfThen(BExpr::Not(Box::new(b)), Box::new(q)),
+            Process::IfThen(b.clone(), Box::new(p), loc),
+            Process::IfThen(BExpr::Not(Box::new(b)), Box::new(q), loc),
         ])
     }
+    pub fn children(&self) -> Vec<&Process> {
+        match self {
+            Process::Constant(..) => vec![],
+            Process::Action(_, p, _)
+            | Process::Restriction(p, _, _)
+            | Process::Hiding(p, _)
+            | Process::Substitution(p, _)
+            | Process::IfThen(_, p, _) => vec![p],
+            Process::Sum(procs) => procs.iter().collect(),
+            Process::Par(p, q) | Process::Disrupt(p, q) => vec![p, q],
+        }
+    }
+    // where x is first called
+    pub fn call_span(&self, x: &str) -> Option<Span> {
+        match self {
+            Process::Constant(y, _, loc) if y == x => loc.span(),
+            _ => self.children().into_iter().find_map(|p| p.call_span(x)),
+        }
+    }
+    // the source this node was parsed from, as far as its prefixes, calls and conditions reach
+    pub fn span(&self) -> Option<Span> {
+        let own = match self {
+            Process::Constant(.., loc) | Process::Action(.., loc) | Process::IfThen(.., loc) => {
+                loc.span()
+            }
+            _ => None,
+        };
+        self.children()
+            .into_iter()
+            .filter_map(Self::span)
+            .chain(own)
+            .reduce(Span::cover)
+    }
 }
 impl Display for Process {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Process::Constant(k, vals) => {
+            Process::Constant(k, vals, _) => {
                 if vals.is_empty() {
                     write!(f, "{k}")
                 } else {
                     write!(f, "{k}({})", vals.iter().map(|v| format!("{v}")).join(","))
                 }
             }
-            Process::Action(ch, p) => {
+            Process::Action(ch, p, _) => {
                 write!(f, "{ch}. {p}")
             }
             Process::Sum(procs) => {
@@ -180,8 +261,12 @@ impl Display for Process {
                 }
             }
             Process::Par(p, q) => write!(f, "({p} | {q})"),
-            Process::Restriction(p, chans) => write!(f, "({p} \\ [{}])", chans.iter().join(", ")),
-            Process::IfThen(b, p) => {
+            Process::Disrupt(p, q) => write!(f, "({p} [> {q})"),
+            Process::Restriction(p, chans, _) => {
+                write!(f, "({p} \\ [{}])", chans.iter().join(", "))
+            }
+            Process::Hiding(p, chans) => write!(f, "({p} / [{}])", chans.iter().join(", ")),
+            Process::IfThen(b, p, _) => {
                 write!(f, "if {b} then {{ {p} }}")
             }
             Process::Substitution(p, subs) => write!(
@@ -219,7 +304,7 @@ impl Display for Channel {
     }
 }
 
-fn encode_action(name: String, v: &Value) -> String {
+pub(crate) fn encode_action(name: String, v: &Value) -> String {
     name + "#" + &v.to_string()
 }
 fn encode_constant(name: String, vals: &[Value]) -> String {
@@ -1,12 +1,14 @@
 use crate::context::Context;
+use crate::error::Result;
 
-pub fn print_values(ctx: Context) {
+pub fn print_values(ctx: Context) -> Result<()> {
     for ty in ctx.types() {
         println!("-------- {}:", ty);
-        for val in ctx.values_of(&ty) {
+        for val in ctx.values_of(&ty)? {
             println!("{val}");
         }
     }
+    Ok(())
 }
 
 pub fn permute<T: Clone>(vals: Vec<Vec<T>>) -> Vec<Vec<T>> {
@@ -3,6 +3,7 @@ use std::fmt::Display;
 use itertools::Itertools;
 
 use super::context::Context;
+use crate::error::{Error, Result};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
@@ -12,13 +13,13 @@ pub enum Value {
     Any(String),
 }
 impl Value {
-    pub fn eval(&self, ctx: &Context) -> Self {
-        match self {
-            Value::AExpr(e) => Value::AExpr(AExpr::Lit(e.eval(ctx))),
-            Value::BExpr(e) => Value::BExpr(BExpr::Lit(e.eval(ctx))),
-            Value::Enum(e) => Value::Enum(e.eval(ctx)),
+    pub fn eval(&self, ctx: &Context) -> Result<Self> {
+        Ok(match self {
+            Value::AExpr(e) => Value::AExpr(AExpr::Lit(e.eval(ctx)?)),
+            Value::BExpr(e) => Value::BExpr(BExpr::Lit(e.eval(ctx)?)),
+            Value::Enum(e) => Value::Enum(e.eval(ctx)?),
             Value::Any(..) => self.clone(),
-        }
+        })
     }
     pub fn try_replace(&mut self, var: &str, val: &Value) -> bool {
         match self {
@@ -55,17 +56,32 @@ pub enum AExpr {
     Div(Box<AExpr>, Box<AExpr>),
 }
 impl AExpr {
-    fn eval(&self, ctx: &Context) -> u32 {
+    fn eval(&self, ctx: &Context) -> Result<u32> {
+        let out_of_bounds = || Error::OutOfBounds(self.to_string(), ctx.bounds());
         let n = match self {
-            AExpr::Var(x) => panic!("[error] free variable \"{x}\" found in expression: {self}"),
+            AExpr::Var(x) => return Err(Error::FreeVariable(x.clone())),
             AExpr::Lit(n) => *n,
-            AExpr::Add(l, r) => l.eval(ctx) + r.eval(ctx),
-            AExpr::Sub(l, r) => l.eval(ctx) - r.eval(ctx),
-            AExpr::Mul(l, r) => l.eval(ctx) * r.eval(ctx),
-            AExpr::Div(l, r) => l.eval(ctx) / r.eval(ctx),
+            AExpr::Add(l, r) => l
+                .eval(ctx)?
+                .checked_add(r.eval(ctx)?)
+                .ok_or_else(out_of_bounds)?,
+            AExpr::Sub(l, r) => l
+                .eval(ctx)?
+                .checked_sub(r.eval(ctx)?)
+                .ok_or_else(out_of_bounds)?,
+            AExpr::Mul(l, r) => l
+                .eval(ctx)?
+                .checked_mul(r.eval(ctx)?)
+                .ok_or_else(out_of_bounds)?,
+            AExpr::Div(l, r) => l
+                .eval(ctx)?
+                .checked_div(r.eval(ctx)?)
+                .ok_or_else(|| Error::DivisionByZero(self.to_string()))?,
         };
-        assert!(n >= ctx.bounds().0 && n < ctx.bounds().1);
-        n
+        if n < ctx.bounds().0 || n >= ctx.bounds().1 {
+            return Err(out_of_bounds());
+        }
+        Ok(n)
     }
     fn try_replace(&mut self, var: &str, val: &Value) -> bool {
         match self {
@@ -115,22 +131,22 @@ pub enum BExpr {
     EnumIs(Enum, Enum),
 }
 impl BExpr {
-    pub fn eval(&self, ctx: &Context) -> bool {
-        match self {
+    pub fn eval(&self, ctx: &Context) -> Result<bool> {
+        Ok(match self {
             BExpr::Lit(true) => true,
             BExpr::Lit(false) => false,
-            BExpr::Var(x) => panic!("[error] free variable {x}"),
-            BExpr::Not(e) => !e.eval(ctx),
-            BExpr::And(l, r) => l.eval(ctx) && r.eval(ctx),
-            BExpr::Or(l, r) => l.eval(ctx) || r.eval(ctx),
-            BExpr::NumEq(l, r) => l.eval(ctx) == r.eval(ctx),
-            BExpr::NumNotEq(l, r) => l.eval(ctx) != r.eval(ctx),
-            BExpr::NumLt(l, r) => l.eval(ctx) < r.eval(ctx),
-            BExpr::NumGt(l, r) => l.eval(ctx) > r.eval(ctx),
-            BExpr::NumLtEq(l, r) => l.eval(ctx) <= r.eval(ctx),
-            BExpr::NumGtEq(l, r) => l.eval(ctx) >= r.eval(ctx),
-            BExpr::EnumIs(l, r) => l.eval(ctx) == r.eval(ctx),
-        }
+            BExpr::Var(x) => return Err(Error::FreeVariable(x.clone())),
+            BExpr::Not(e) => !e.eval(ctx)?,
+            BExpr::And(l, r) => l.eval(ctx)? && r.eval(ctx)?,
+            BExpr::Or(l, r) => l.eval(ctx)? || r.eval(ctx)?,
+            BExpr::NumEq(l, r) => l.eval(ctx)? == r.eval(ctx)?,
+            BExpr::NumNotEq(l, r) => l.eval(ctx)? != r.eval(ctx)?,
+            BExpr::NumLt(l, r) => l.eval(ctx)? < r.eval(ctx)?,
+            BExpr::NumGt(l, r) => l.eval(ctx)? > r.eval(ctx)?,
+            BExpr::NumLtEq(l, r) => l.eval(ctx)? <= r.eval(ctx)?,
+            BExpr::NumGtEq(l, r) => l.eval(ctx)? >= r.eval(ctx)?,
+            BExpr::EnumIs(l, r) => l.eval(ctx)? == r.eval(ctx)?,
+        })
     }
     pub fn try_replace(&mut self, var: &str, val: &Value) -> bool {
         match self {
@@ -184,29 +200,36 @@ pub enum Enum {
     Lit(String, String, Vec<Value>),
 }
 impl Enum {
-    pub fn eval(&self, ctx: &Context) -> Self {
+    pub fn eval(&self, ctx: &Context) -> Result<Self> {
         match self {
-            Enum::Var(_) => todo!(),
+            Enum::Var(x) => Err(Error::FreeVariable(x.clone())),
             Enum::Lit(ty, tag, vals) => {
                 let types = &ctx
                     .enums()
-                    .iter()
-                    .find(|t| t.0 == ty)
-                    .unwrap()
-                    .1
+                    .get(ty)
+                    .ok_or_else(|| Error::UndefinedType(ty.clone()))?
                     .iter()
                     .find(|t| t.0 == *tag)
-                    .unwrap()
+                    .ok_or_else(|| Error::UndefinedTag(ty.clone(), tag.clone()))?
                     .1;
-                assert_eq!(vals.len(), types.len());
+                if vals.len() != types.len() {
+                    return Err(Error::ArityMismatch(
+                        format!("{ty}::{tag}"),
+                        types.len(),
+                        vals.len(),
+                    ));
+                }
                 for (v, t) in vals.iter().zip(types.iter()) {
-                    assert_eq!(ctx.type_of(v), t);
+                    let found = ctx.type_of(v)?;
+                    if found != t {
+                        return Err(Error::TypeMismatch(t.clone(), found.to_string()));
+                    }
                 }
-                Self::Lit(
+                Ok(Self::Lit(
                     ty.clone(),
                     tag.clone(),
-                    vals.iter().map(|v| v.eval(ctx)).collect(),
-                )
+                    vals.iter().map(|v| v.eval(ctx)).collect::<Result<_>>()?,
+                ))
             }
         }
     }
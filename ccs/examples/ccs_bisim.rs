@@ -10,8 +10,8 @@ fn main() {
     let ch1 = Channel::send("ch1");
     let ch2 = Channel::send("ch2");
 
-    let lts1 = Lts::from_array([(&a, &ch1, &b)]);
-    let lts2 = Lts::from_array([(&c, &ch2, &d)]);
+    let lts1 = Lts::from_array([(&a, &ch1, &b)]).unwrap();
+    let lts2 = Lts::from_array([(&c, &ch2, &d)]).unwrap();
 
     let b = lts1.bisimilarity(&lts2);
     print_bisimulation(&b);
@@ -0,0 +1,29 @@
+use ccs::context::Context;
+use ccs::cost::MaxCost;
+use ccs::process::Channel;
+
+fn main() {
+    let source = include_str!("ccs_cost.ccs");
+    let ctx = Context::try_from(source).unwrap();
+    let lts = ctx.to_lts().unwrap();
+    let commit = Channel::send("commit");
+    if let Some((cost, path)) = lts.min_cost(&ctx, &commit) {
+        println!("cheapest commit! = {cost}");
+        for (p, a, q) in path {
+            println!("  {p} --{a}--> {q}");
+        }
+    }
+    match lts.max_cost(&ctx, &commit) {
+        MaxCost::Bounded(cost, path) => {
+            println!("costliest commit! = {cost}");
+            for (p, a, q) in path {
+                println!("  {p} --{a}--> {q}");
+            }
+        }
+        MaxCost::Unbounded => println!("costliest commit! is unbounded"),
+        MaxCost::Unreachable => println!("commit! is unreachable"),
+    }
+    for cycle in lts.positive_cycles(&ctx) {
+        println!("positive cycle through {} states", cycle.len());
+    }
+}
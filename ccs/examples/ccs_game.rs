@@ -0,0 +1,14 @@
+use ccs::context::Context;
+use ccs::game::bisimilar;
+use ccs::process::{Explorer, Process};
+use ccs::utils::print_game;
+
+fn main() {
+    let source = include_str!("ccs_game.ccs");
+    let ctx = Context::try_from(source).unwrap();
+    let t = Explorer::new(&ctx, Process::constant("Impl"));
+    let u = Explorer::new(&ctx, Process::constant("Spec"));
+    if let Err(cex) = bisimilar(&t, &u) {
+        print_game(&t, &u, &cex);
+    }
+}
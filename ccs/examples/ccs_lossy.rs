@@ -0,0 +1,14 @@
+use ccs::context::Context;
+use ccs::process::Channel;
+
+fn main() {
+    let source = include_str!("ccs_lossy.ccs");
+    let ctx = Context::try_from(source).unwrap();
+    let pts = ctx.to_pts().unwrap();
+    let lost = pts.enabling(&Channel::send("lost"));
+    let deliver = pts.enabling(&Channel::send("deliver"));
+    let first = |targets| pts.reachability(targets, true)[pts.init()];
+    println!("P(reach lost!) = {}", first(&lost));
+    println!("P(reach deliver!) = {}", first(&deliver));
+    print!("{}", pts.to_prism_tra());
+}
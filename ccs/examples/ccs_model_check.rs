@@ -0,0 +1,28 @@
+use ccs::context::Context;
+use ccs::model_check::Formula;
+use ccs::process::Channel;
+
+fn main() {
+    let source = include_str!("ccs_model_check.ccs");
+    let ctx = Context::try_from(source).unwrap();
+    let lts = ctx.to_lts().unwrap();
+    let work = || Formula::diamond(Some(Channel::send("work")), Formula::True);
+    let formulas = [
+        (
+            "deadlock free",
+            Formula::always(Formula::negate(Formula::deadlock())),
+        ),
+        (
+            "work is always possible",
+            Formula::always(Formula::possibly(work())),
+        ),
+        ("work is inevitable", Formula::inevitably(work())),
+        (
+            "work can happen twice in a row",
+            Formula::possibly(Formula::diamond(Some(Channel::send("work")), work())),
+        ),
+    ];
+    for (name, f) in formulas {
+        println!("{name}: {}", lts.satisfies(&f));
+    }
+}
@@ -0,0 +1,20 @@
+use ccs::context::Context;
+use ccs::process::Channel;
+
+fn main() {
+    let source = include_str!("ccs_queue.ccs");
+    let ctx = Context::try_from(source).unwrap();
+    let ctmc = ctx.to_ctmc().unwrap();
+    let steady = ctmc.steady_state();
+    let serving = ctmc.enabling(&Channel::send("serve"));
+    println!(
+        "throughput(serve!) = {}",
+        ctmc.throughput(&steady, &Channel::send("serve"))
+    );
+    println!("utilisation = {}", ctmc.probability(&steady, &serving));
+    println!(
+        "utilisation(t = 1) = {}",
+        ctmc.probability(&ctmc.transient(1.0), &serving)
+    );
+    print!("{}", ctmc.to_matrix_market());
+}
@@ -1,17 +1,40 @@
 use std::fmt::Display;
 
-use lalrpop_util::lexer::Token;
-use lalrpop_util::{lalrpop_mod, ParseError};
+use lalrpop_util::lalrpop_mod;
 
 use super::process::Process;
+use crate::diagnostic::Spanned;
+use crate::error::{self, Error};
 
-lalrpop_mod!(pub ccs);
+lalrpop_mod!(#[allow(clippy::ptr_arg)] pub ccs);
 
-pub struct Program(pub Vec<Statement>);
-impl<'a> TryFrom<&'a str> for Program {
-    type Error = ParseError<usize, Token<'a>, &'static str>;
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        ccs::ProgramNodeParser::new().parse(value)
+#[derive(Debug, Clone)]
+pub struct Program(pub Vec<Spanned<Statement>>);
+impl Program {
+    pub fn parse(source: &str) -> (Self, Vec<Error>) {
+        let mut recovered = vec![];
+        let result = ccs::ProgramNodeParser::new().parse(&mut recovered, source);
+        let mut errors = recovered
+            .into_iter()
+            .map(|e| Error::from(e.error))
+            .collect::<Vec<_>>();
+        match result {
+            Ok(program) => (program, errors),
+            Err(e) => {
+                errors.push(Error::from(e));
+                (Self(vec![]), errors)
+            }
+        }
+    }
+}
+impl TryFrom<&str> for Program {
+    type Error = Error;
+    fn try_from(value: &str) -> error::Result<Self> {
+        let (program, errors) = Self::parse(value);
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(program),
+        }
     }
 }
 impl Display for Program {
@@ -25,7 +48,8 @@ impl Display for Program {
 
 #[derive(Debug, Clone)]
 pub enum Statement {
-    DefConstant(String, Process),
+    DefConstant(Spanned<String>, Process),
+    DefChannel(String, Option<u32>),
     Exec(Command),
 }
 impl Display for Statement {
@@ -34,6 +58,8 @@ impl Display for Statement {
             Statement::DefConstant(name, p) => {
                 write!(f, "fn {name}() {{ {p} }}")
             }
+            Statement::DefChannel(name, Some(n)) => write!(f, "chan {name}: buffered({n});"),
+            Statement::DefChannel(name, None) => write!(f, "chan {name}: async;"),
             Statement::Exec(c) => write!(f, "#![{c:?}]"),
         }
     }
@@ -42,4 +68,9 @@ impl Display for Statement {
 #[derive(Debug, Clone)]
 pub enum Command {
     SetMain(String),
+    Broadcast(Vec<String>),
+    Replication(u32),
+    Priority(String, u32),
+    Cost(String, u32),
+    Timed,
 }
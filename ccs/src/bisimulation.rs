@@ -0,0 +1,129 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use itertools::Itertools;
+
+use crate::transition_system::{reachable, TransitionSystem};
+
+#[derive(Clone, Debug)]
+pub struct Bisimulation<S, T> {
+    left: HashMap<S, usize>,
+    right: HashMap<T, usize>,
+    classes: usize,
+}
+impl<S: Clone + Eq + Hash, T: Clone + Eq + Hash> Bisimulation<S, T> {
+    pub fn are_equivalent(&self, p: &S, q: &T) -> bool {
+        matches!((self.left.get(p), self.right.get(q)), (Some(a), Some(b)) if a == b)
+    }
+    pub fn left_class(&self, p: &S) -> Option<usize> {
+        self.left.get(p).copied()
+    }
+    pub fn right_class(&self, q: &T) -> Option<usize> {
+        self.right.get(q).copied()
+    }
+    pub fn classes(&self) -> Vec<(Vec<&S>, Vec<&T>)> {
+        let mut classes = vec![(vec![], vec![]); self.classes];
+        for (p, &id) in &self.left {
+            classes[id].0.push(p);
+        }
+        for (q, &id) in &self.right {
+            classes[id].1.push(q);
+        }
+        classes
+    }
+    pub fn certificate(&self) -> HashSet<(S, T)> {
+        self.left
+            .iter()
+            .cartesian_product(self.right.iter())
+            .filter(|((_, a), (_, b))| a == b)
+            .map(|((p, _), (q, _))| (p.clone(), q.clone()))
+            .collect()
+    }
+}
+
+pub fn bisimilarity<T, U>(t: &T, u: &U) -> Bisimulation<T::State, U::State>
+where
+    T: TransitionSystem,
+    U: TransitionSystem<Label = T::Label>,
+{
+    let left = reachable(t).into_iter().collect_vec();
+    let right = reachable(u).into_iter().collect_vec();
+    let lindex: HashMap<_, _> = left.iter().enumerate().map(|(i, p)| (p, i)).collect();
+    let rindex: HashMap<_, _> = right
+        .iter()
+        .enumerate()
+        .map(|(i, q)| (q, left.len() + i))
+        .collect();
+
+    let mut labels = HashMap::new();
+    let mut label_id = |l: T::Label| {
+        let n = labels.len();
+        *labels.entry(l).or_insert(n)
+    };
+    let mut succ = vec![];
+    for p in &left {
+        let next = t.successors(p);
+        succ.push(
+            next.into_iter()
+                .map(|(a, p2)| (label_id(a), lindex[&p2]))
+                .collect_vec(),
+        );
+    }
+    for q in &right {
+        let next = u.successors(q);
+        succ.push(
+            next.into_iter()
+                .map(|(a, q2)| (label_id(a), rindex[&q2]))
+                .collect_vec(),
+        );
+    }
+
+    let mut class = vec![0; succ.len()];
+    let mut classes = 1;
+    loop {
+        let mut ids = HashMap::new();
+        let refined = (0..succ.len())
+            .map(|i| {
+                let sig = succ[i]
+                    .iter()
+                    .map(|&(a, j)| (a, class[j]))
+                    .sorted()
+                    .dedup()
+                    .collect_vec();
+                let n = ids.len();
+                *ids.entry((class[i], sig)).or_insert(n)
+            })
+            .collect_vec();
+        class = refined;
+        if ids.len() == classes {
+            break;
+        }
+        classes = ids.len();
+    }
+
+    let n = left.len();
+    Bisimulation {
+        left: left.into_iter().zip(class[..n].iter().copied()).collect(),
+        right: right.into_iter().zip(class[n..].iter().copied()).collect(),
+        classes,
+    }
+}
+
+pub fn is_bisimulation<T, U>(t: &T, u: &U, r: &HashSet<(T::State, U::State)>) -> bool
+where
+    T: TransitionSystem,
+    U: TransitionSystem<Label = T::Label>,
+{
+    r.iter().all(|(p, q)| {
+        let (pnext, qnext) = (t.successors(p), u.successors(q));
+        pnext.iter().all(|(a, p2)| {
+            qnext
+                .iter()
+                .any(|(b, q2)| a == b && r.contains(&(p2.clone(), q2.clone())))
+        }) && qnext.iter().all(|(b, q2)| {
+            pnext
+                .iter()
+                .any(|(a, p2)| a == b && r.contains(&(p2.clone(), q2.clone())))
+        })
+    })
+}
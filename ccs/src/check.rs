@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::ast::{Command, Program, Statement};
+use crate::context::Context;
+use crate::diagnostic::{line_col, Diagnostic, Span};
+use crate::error::Error;
+use crate::process::{Channel, Process};
+
+pub fn check(program: &Program, source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut ctx = Context::new();
+    let mut defined: HashMap<&str, Span> = HashMap::new();
+    let mut main = None;
+    for stmt in &program.0 {
+        match &stmt.node {
+            Statement::DefConstant(name, p) => {
+                if let Some(first) = defined.get(name.node.as_str()) {
+                    let (line, _) = line_col(source, first.start);
+                    let e = Error::DuplicateConstant(name.node.clone()).at(Some(name.span));
+                    diagnostics.push(
+                        e.diagnostic()
+                            .with_hint(format!("first defined on line {line}")),
+                    );
+                } else {
+                    defined.insert(name.node.as_str(), name.span);
+                    ctx.bind_process(name.node.clone(), p.clone());
+                }
+            }
+            Statement::Exec(Command::SetMain(name)) => main = Some((name, stmt.span)),
+            _ => {}
+        }
+    }
+
+    match main {
+        None => diagnostics.push(Error::MissingMain.diagnostic()),
+        Some((name, span)) if !defined.contains_key(name.as_str()) => {
+            let e = Error::UndefinedMain(name.clone()).at(Some(span));
+            diagnostics.push(e.diagnostic());
+        }
+        Some(_) => {}
+    }
+
+    let table = channel_table(&ctx);
+    for stmt in &program.0 {
+        let Statement::DefConstant(name, p) = &stmt.node else {
+            continue;
+        };
+        for x in p.free_vars().into_iter().sorted() {
+            if !defined.contains_key(x.as_str()) {
+                let span = p.call_span(&x);
+                diagnostics.push(Error::UndefinedConstant(x).at(span).diagnostic());
+            }
+        }
+        if defined.get(name.node.as_str()) == Some(&name.span) {
+            if let Some(span) = ctx.unguarded_call_span(&name.node) {
+                let e = Error::UnguardedRecursion(name.node.clone()).at(span);
+                diagnostics.push(e.diagnostic());
+            }
+        }
+        for (x, span) in p.unguarded_recs() {
+            diagnostics.push(Error::UnguardedRecursion(x).at(span).diagnostic());
+        }
+        check_scopes(p, &table, &mut diagnostics);
+    }
+    diagnostics.sort_by_key(|d| d.span.map(|s| s.start));
+    diagnostics
+}
+
+fn check_scopes(
+    p: &Process,
+    table: &HashMap<String, HashSet<Channel>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let unused = |q: &Process, chans: Vec<&String>| {
+        let used = channels(q, table);
+        chans
+            .into_iter()
+            .filter(|x| !used.iter().any(|ch| ch.name() == *x))
+            .cloned()
+            .collect_vec()
+    };
+    match p {
+        Process::Restriction(q, chans, loc) => {
+            for x in unused(q, chans.iter().collect()) {
+                let message = format!("restricted channel \"{x}\" never occurs in its scope");
+                diagnostics.push(Diagnostic::warning(message, loc.span()));
+            }
+        }
+        Process::Substitution(q, subs) => {
+            for x in unused(q, subs.pairs().iter().map(|(_, old)| old).collect()) {
+                let message = format!("substituted channel \"{x}\" never occurs in its scope");
+                diagnostics.push(Diagnostic::warning(message, subs.span()));
+            }
+        }
+        _ => {}
+    }
+    for q in p.children() {
+        check_scopes(q, table, diagnostics);
+    }
+}
+
+pub fn channel_table(ctx: &Context) -> HashMap<String, HashSet<Channel>> {
+    let mut table = HashMap::<String, HashSet<Channel>>::new();
+    loop {
+        let mut changed = false;
+        for (name, p) in ctx.constants() {
+            let chans = channels(p, &table);
+            let entry = table.entry(name.clone()).or_default();
+            if chans.len() > entry.len() {
+                *entry = chans;
+                changed = true;
+            }
+        }
+        if !changed {
+            return table;
+        }
+    }
+}
+
+pub fn channels(p: &Process, table: &HashMap<String, HashSet<Channel>>) -> HashSet<Channel> {
+    match p {
+        Process::Constant(x, _) => table.get(x).cloned().unwrap_or_default(),
+        Process::Action(ch @ (Channel::Send(_) | Channel::Recv(_)), q, _)
+        | Process::Rated(ch @ (Channel::Send(_) | Channel::Recv(_)), _, q, _) => {
+            let mut chans = channels(q, table);
+            chans.insert(ch.clone());
+            chans
+        }
+        Process::Restriction(q, names, _) | Process::Hiding(q, names) => {
+            let mut chans = channels(q, table);
+            chans.retain(|ch| !names.iter().any(|x| x == ch.name()));
+            chans
+        }
+        Process::Substitution(q, subs) => channels(q, table)
+            .into_iter()
+            .map(|ch| subs.replace_channel(ch))
+            .collect(),
+        _ => p
+            .children()
+            .into_iter()
+            .flat_map(|q| channels(q, table))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each diagnostic's message, start offset and the source text it covers.
+    fn located(source: &str) -> Vec<(String, usize, &str)> {
+        let program = Program::try_from(source).unwrap();
+        check(&program, source)
+            .into_iter()
+            .map(|d| {
+                let span = d.span.expect("diagnostic without a location");
+                (d.message, span.start, &source[span.start..span.end])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unused_restriction_points_at_its_channel_set() {
+        let source = "#![start(Main)]\nfn Main() { (b!.nil | (a!.nil \\ {b})) }";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1, source.find("{b}").unwrap());
+        assert_eq!(diagnostics[0].2, "{b}");
+    }
+
+    #[test]
+    fn unused_substitution_points_at_its_pairs() {
+        let source = "#![start(Main)]\nfn Main() { (b!.nil | (a!.nil[c/b])) }";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].2, "c/b");
+    }
+
+    #[test]
+    fn undefined_constant_points_at_the_call() {
+        let source = "#![start(Main)]\nfn Main() { a!.Missing() }";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].0, "constant \"Missing\" is not defined");
+        assert_eq!(diagnostics[0].2, "Missing()");
+    }
+
+    #[test]
+    fn duplicate_constant_points_at_the_second_name() {
+        let source = "#![start(Main)]\nfn Main() { nil }\nfn Main() { skip }";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1, source.rfind("Main").unwrap());
+    }
+
+    #[test]
+    fn unguarded_recursion_points_at_the_call() {
+        let source = "#![start(Main)]\nfn Main() { (a!.nil + Main()) }";
+        let diagnostics = located(source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].1, source.rfind("Main()").unwrap());
+        assert_eq!(diagnostics[0].2, "Main()");
+    }
+}
@@ -1,19 +1,32 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use lalrpop_util::lexer::Token;
-use lalrpop_util::ParseError;
+use itertools::Itertools;
 
 use super::ast::{Program, Statement};
-use super::process::Process;
+use super::process::{Channel, Process};
 use crate::ast::Command;
+use crate::ctmc::Ctmc;
+use crate::diagnostic::Span;
+use crate::error::{Error, Result};
 use crate::lts::Lts;
+use crate::pts::Pts;
 
 #[derive(Debug, Clone, Default)]
 pub struct Context {
     main: String,
     constants: HashMap<String, Process>,
+    normal_forms: HashMap<Process, String>,
+    broadcasts: HashSet<String>,
+    buffers: HashMap<String, Option<u32>>,
+    buffered_names: HashMap<String, String>,
+    replication: Option<u32>,
+    priorities: HashMap<String, u32>,
+    costs: HashMap<String, u32>,
+    timed: bool,
 }
 impl Context {
+    const DEFAULT_REPLICATION: u32 = 3;
+
     pub fn new() -> Self {
         Self::default()
     }
@@ -21,6 +34,8 @@ impl Context {
         &self.constants
     }
     pub fn bind_process(&mut self, name: String, p: Process) {
+        self.normal_forms
+            .insert(p.clone().normalize(), name.clone());
         self.constants.insert(name, p);
     }
     pub fn get_process(&self, name: &str) -> Option<&Process> {
@@ -34,34 +49,160 @@ impl Context {
             .iter()
             .find(|(_, process)| *process == p)
             .map(|(id, _)| id.as_str())
+            .or_else(|| self.normal_forms.get(p).map(String::as_str))
+    }
+    pub fn bind_broadcast(&mut self, chan: String) {
+        self.broadcasts.insert(chan);
+    }
+    pub fn is_broadcast(&self, chan: &str) -> bool {
+        self.broadcasts.contains(chan)
+    }
+    pub fn bind_buffer(&mut self, chan: String, capacity: Option<u32>, names: Vec<String>) {
+        for name in names {
+            self.buffered_names.insert(name, chan.clone());
+        }
+        self.buffers.insert(chan, capacity);
+    }
+    pub fn buffers(&self) -> &HashMap<String, Option<u32>> {
+        &self.buffers
+    }
+    pub fn buffer_of(&self, name: &str) -> Option<&str> {
+        self.buffered_names.get(name).map(String::as_str)
+    }
+    pub fn buffer_has_room(&self, chan: &str, len: usize) -> bool {
+        match self.buffers.get(chan) {
+            Some(Some(capacity)) => len < *capacity as usize,
+            _ => true,
+        }
+    }
+    pub fn set_replication(&mut self, bound: u32) {
+        self.replication = Some(bound);
+    }
+    pub fn replication_bound(&self) -> u32 {
+        self.replication.unwrap_or(Self::DEFAULT_REPLICATION)
+    }
+    pub fn bind_priority(&mut self, name: String, level: u32) {
+        self.priorities.insert(name, level);
+    }
+    pub fn priority_of(&self, ch: &Channel) -> u32 {
+        self.priorities.get(ch.name()).copied().unwrap_or_default()
+    }
+    pub fn bind_cost(&mut self, name: String, cost: u32) {
+        self.costs.insert(name, cost);
+    }
+    pub fn cost_of(&self, ch: &Channel) -> u32 {
+        self.costs.get(ch.name()).copied().unwrap_or_default()
+    }
+    pub fn set_timed(&mut self, timed: bool) {
+        self.timed = timed;
+    }
+    pub fn is_timed(&self) -> bool {
+        self.timed
     }
     pub fn set_main(&mut self, main: String) {
         self.main = main;
     }
-    pub fn to_lts(&self) -> Lts {
-        self.constants
-            .get(&self.main)
-            .unwrap_or_else(|| panic!("Main process \"{}\" not found", self.main));
-        Process::constant(&self.main).derive_lts(self)
+    pub fn unguarded_calls(&self, name: &str) -> HashSet<String> {
+        let mut calls = HashSet::new();
+        let mut stack = vec![name.to_string()];
+        while let Some(name) = stack.pop() {
+            let Some(p) = self.get_process(&name) else {
+                continue;
+            };
+            for x in p.unguarded_consts() {
+                if calls.insert(x.clone()) {
+                    stack.push(x);
+                }
+            }
+        }
+        calls
+    }
+    // the call that starts an unguarded cycle back to the constant, if there is one
+    pub fn unguarded_call_span(&self, name: &str) -> Option<Option<Span>> {
+        let p = self.get_process(name)?;
+        p.unguarded_consts()
+            .into_iter()
+            .sorted()
+            .find(|x| x == name || self.unguarded_calls(x).contains(name))
+            .map(|x| p.call_span(&x))
+    }
+    pub fn check(&self) -> Result<()> {
+        if self.main.is_empty() {
+            return Err(Error::MissingMain);
+        }
+        if !self.constants.contains_key(&self.main) {
+            return Err(Error::UndefinedMain(self.main.clone()));
+        }
+        let names = self.constants.keys().sorted().collect_vec();
+        for name in &names {
+            let p = &self.constants[*name];
+            if let Some(x) = p
+                .free_vars()
+                .into_iter()
+                .sorted()
+                .find(|x| !self.constants.contains_key(x))
+            {
+                let span = p.call_span(&x);
+                return Err(Error::UndefinedConstant(x).at(span));
+            }
+        }
+        for name in &names {
+            if let Some(span) = self.unguarded_call_span(name) {
+                return Err(Error::UnguardedRecursion(name.to_string()).at(span));
+            }
+        }
+        match names
+            .into_iter()
+            .find_map(|x| self.constants[x].unguarded_recs().into_iter().next())
+        {
+            Some((x, span)) => Err(Error::UnguardedRecursion(x).at(span)),
+            None => Ok(()),
+        }
+    }
+    pub fn to_lts(&self) -> Result<Lts> {
+        self.check()?;
+        Ok(Process::constant(&self.main).derive_lts(self))
+    }
+    pub fn to_pts(&self) -> Result<Pts> {
+        self.check()?;
+        Ok(Pts::new(self, Process::constant(&self.main)))
+    }
+    pub fn to_ctmc(&self) -> Result<Ctmc> {
+        self.check()?;
+        Ok(Ctmc::new(self, Process::constant(&self.main)))
     }
 }
-impl From<Program> for Context {
-    fn from(value: Program) -> Self {
+impl TryFrom<Program> for Context {
+    type Error = Error;
+    fn try_from(value: Program) -> Result<Self> {
         let mut ctx = Self::default();
         for stmt in value.0 {
-            match stmt {
-                Statement::DefConstant(name, def) => ctx.bind_process(name, def.clone()),
+            match stmt.node {
+                Statement::DefConstant(name, _) if ctx.constants.contains_key(&name.node) => {
+                    return Err(Error::DuplicateConstant(name.node).at(Some(name.span)));
+                }
+                Statement::DefConstant(name, def) => ctx.bind_process(name.node, def),
+                Statement::DefChannel(name, capacity) => {
+                    ctx.bind_buffer(name.clone(), capacity, vec![name])
+                }
                 Statement::Exec(cmd) => match cmd {
                     Command::SetMain(main) => ctx.set_main(main),
+                    Command::Replication(bound) => ctx.set_replication(bound),
+                    Command::Priority(name, level) => ctx.bind_priority(name, level),
+                    Command::Cost(name, cost) => ctx.bind_cost(name, cost),
+                    Command::Timed => ctx.set_timed(true),
+                    Command::Broadcast(chans) => {
+                        chans.into_iter().for_each(|ch| ctx.bind_broadcast(ch))
+                    }
                 },
             }
         }
-        ctx
+        Ok(ctx)
     }
 }
-impl<'a> TryFrom<&'a str> for Context {
-    type Error = ParseError<usize, Token<'a>, &'static str>;
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        Ok(Self::from(Program::try_from(value)?))
+impl TryFrom<&str> for Context {
+    type Error = Error;
+    fn try_from(value: &str) -> Result<Self> {
+        Self::try_from(Program::try_from(value)?)
     }
 }
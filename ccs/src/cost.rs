@@ -0,0 +1,292 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use itertools::Itertools;
+
+use crate::transition_system::{reachable, TransitionSystem};
+
+pub type Step<T> = (
+    <T as TransitionSystem>::State,
+    <T as TransitionSystem>::Label,
+    <T as TransitionSystem>::State,
+);
+
+struct Indexed<S, L> {
+    states: Vec<S>,
+    edges: Vec<Vec<(L, usize)>>,
+}
+impl<S: Clone + Eq + Hash, L> Indexed<S, L> {
+    fn new<T: TransitionSystem<State = S, Label = L>>(ts: &T) -> Self {
+        let init = ts.initial();
+        let states = [init.clone()]
+            .into_iter()
+            .chain(reachable(ts).into_iter().filter(|s| *s != init))
+            .collect_vec();
+        let index = states
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), i))
+            .collect::<HashMap<_, _>>();
+        let edges = states
+            .iter()
+            .map(|s| {
+                ts.successors(s)
+                    .into_iter()
+                    .map(|(l, t)| (l, index[&t]))
+                    .collect()
+            })
+            .collect();
+        Self { states, edges }
+    }
+    fn inner_path(&self, component: &[usize], from: usize, to: usize) -> Vec<(S, L, S)>
+    where
+        L: Clone,
+    {
+        let mut prev = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(s) = queue.pop_front() {
+            if s == to {
+                break;
+            }
+            for (i, (_, t)) in self.edges[s].iter().enumerate() {
+                if component[*t] == component[from] && !prev.contains_key(t) {
+                    prev.insert(*t, Some((s, i)));
+                    queue.push_back(*t);
+                }
+            }
+        }
+        let mut path = vec![];
+        let mut s = to;
+        while let Some(Some((p, i))) = prev.get(&s) {
+            let (l, _) = &self.edges[*p][*i];
+            path.push((self.states[*p].clone(), l.clone(), self.states[s].clone()));
+            s = *p;
+        }
+        path.reverse();
+        path
+    }
+    fn components(&self) -> (Vec<usize>, Vec<Vec<usize>>) {
+        struct Tarjan<'a, L> {
+            edges: &'a [Vec<(L, usize)>],
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            component: Vec<usize>,
+            next: usize,
+            count: usize,
+        }
+        impl<L> Tarjan<'_, L> {
+            fn open(&mut self, s: usize) {
+                self.index[s] = Some(self.next);
+                self.low[s] = self.next;
+                self.next += 1;
+                self.stack.push(s);
+                self.on_stack[s] = true;
+            }
+            // iterative, so that long chains of states cannot overflow the call stack
+            fn visit(&mut self, root: usize) {
+                self.open(root);
+                let mut calls = vec![(root, 0)];
+                while let Some((s, i)) = calls.pop() {
+                    if let Some((_, t)) = self.edges[s].get(i) {
+                        let t = *t;
+                        calls.push((s, i + 1));
+                        match self.index[t] {
+                            None => {
+                                self.open(t);
+                                calls.push((t, 0));
+                            }
+                            Some(index) if self.on_stack[t] => self.low[s] = self.low[s].min(index),
+                            Some(_) => {}
+                        }
+                        continue;
+                    }
+                    if let Some((p, _)) = calls.last() {
+                        self.low[*p] = self.low[*p].min(self.low[s]);
+                    }
+                    if Some(self.low[s]) == self.index[s] {
+                        while let Some(t) = self.stack.pop() {
+                            self.on_stack[t] = false;
+                            self.component[t] = self.count;
+                            if t == s {
+                                break;
+                            }
+                        }
+                        self.count += 1;
+                    }
+                }
+            }
+        }
+        let n = self.states.len();
+        let mut tarjan = Tarjan {
+            edges: &self.edges,
+            index: vec![None; n],
+            low: vec![0; n],
+            stack: vec![],
+            on_stack: vec![false; n],
+            component: vec![0; n],
+            next: 0,
+            count: 0,
+        };
+        for s in 0..n {
+            if tarjan.index[s].is_none() {
+                tarjan.visit(s);
+            }
+        }
+        let mut members = vec![vec![]; tarjan.count];
+        for (s, c) in tarjan.component.iter().enumerate() {
+            members[*c].push(s);
+        }
+        (tarjan.component, members)
+    }
+}
+
+pub fn min_cost<T: TransitionSystem>(
+    ts: &T,
+    cost: impl Fn(&T::Label) -> u32,
+    target: &T::Label,
+) -> Option<(u32, Vec<Step<T>>)> {
+    let graph = Indexed::new(ts);
+    let mut dist = vec![None; graph.states.len()];
+    let mut prev = vec![None; graph.states.len()];
+    let mut best: Option<(u32, usize, usize)> = None;
+    let mut queue = BinaryHeap::from([Reverse((0, 0))]);
+    dist[0] = Some(0);
+    while let Some(Reverse((d, s))) = queue.pop() {
+        if best.is_some_and(|(c, ..)| c <= d) {
+            break;
+        }
+        if dist[s] != Some(d) {
+            continue;
+        }
+        for (i, (l, t)) in graph.edges[s].iter().enumerate() {
+            // costs stop at u32::MAX rather than wrap around
+            let c = d.saturating_add(cost(l));
+            if l == target && best.is_none_or(|(b, ..)| c < b) {
+                best = Some((c, s, i));
+            }
+            if dist[*t].is_none_or(|b| c < b) {
+                dist[*t] = Some(c);
+                prev[*t] = Some((s, i));
+                queue.push(Reverse((c, *t)));
+            }
+        }
+    }
+    let (c, mut s, i) = best?;
+    let (l, t) = &graph.edges[s][i];
+    let mut path = vec![(graph.states[s].clone(), l.clone(), graph.states[*t].clone())];
+    while let Some((p, i)) = prev[s] {
+        let (l, _) = &graph.edges[p][i];
+        path.push((graph.states[p].clone(), l.clone(), graph.states[s].clone()));
+        s = p;
+    }
+    path.reverse();
+    Some((c, path))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MaxCost<S> {
+    Bounded(u32, Vec<S>),
+    Unbounded,
+    Unreachable,
+}
+
+pub fn max_cost<T: TransitionSystem>(
+    ts: &T,
+    cost: impl Fn(&T::Label) -> u32,
+    target: &T::Label,
+) -> MaxCost<Step<T>> {
+    let graph = Indexed::new(ts);
+    let (component, members) = graph.components();
+    // tarjan numbers components in reverse topological order
+    let mut value: Vec<Option<u32>> = vec![None; members.len()];
+    let mut choice = vec![None; members.len()];
+    for c in 0..members.len() {
+        let mut positive = false;
+        for s in &members[c] {
+            for (i, (l, t)) in graph.edges[*s].iter().enumerate() {
+                let k = cost(l);
+                let inner = component[*t] == c;
+                positive |= inner && k > 0;
+                let v = match (l == target, inner) {
+                    (true, _) => Some(k),
+                    (false, false) => value[component[*t]].map(|v| v.saturating_add(k)),
+                    (false, true) => None,
+                };
+                if v > value[c] {
+                    value[c] = v;
+                    choice[c] = Some((*s, i));
+                }
+            }
+        }
+        if positive && value[c].is_some() {
+            return MaxCost::Unbounded;
+        }
+    }
+    let Some(c) = value[component[0]] else {
+        return MaxCost::Unreachable;
+    };
+
+    // within a component on the path every edge is free, so any inner route will do
+    let mut path = vec![];
+    let mut s = 0;
+    loop {
+        let (u, i) = choice[component[s]].expect("a component with a value has a choice");
+        path.extend(graph.inner_path(&component, s, u));
+        let (l, t) = &graph.edges[u][i];
+        path.push((graph.states[u].clone(), l.clone(), graph.states[*t].clone()));
+        if l == target {
+            return MaxCost::Bounded(c, path);
+        }
+        s = *t;
+    }
+}
+
+pub fn positive_cycles<T: TransitionSystem>(
+    ts: &T,
+    cost: impl Fn(&T::Label) -> u32,
+) -> Vec<Vec<T::State>> {
+    let graph = Indexed::new(ts);
+    let (component, members) = graph.components();
+    members
+        .into_iter()
+        .enumerate()
+        .filter(|(c, states)| {
+            states.iter().any(|s| {
+                graph.edges[*s]
+                    .iter()
+                    .any(|(l, t)| component[*t] == *c && cost(l) > 0)
+            })
+        })
+        .map(|(_, states)| {
+            states
+                .into_iter()
+                .map(|s| graph.states[s].clone())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use crate::process::Channel;
+
+    #[test]
+    fn costs_saturate_instead_of_overflowing() {
+        let source = "#![start(Main)]\n#![cost(a, 4294967295)]\n#![cost(b, 1)]\n\
+                      fn Main() { a!.b!.commit!.nil }";
+        let ctx = Context::try_from(source).unwrap();
+        let lts = ctx.to_lts().unwrap();
+        let commit = Channel::send("commit");
+        assert_eq!(lts.min_cost(&ctx, &commit).map(|(c, _)| c), Some(u32::MAX));
+        let MaxCost::Bounded(c, path) = lts.max_cost(&ctx, &commit) else {
+            panic!("commit! should be reachable at a bounded cost");
+        };
+        assert_eq!(c, u32::MAX);
+        assert_eq!(path.len(), 3);
+    }
+}
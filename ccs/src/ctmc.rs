@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::context::Context;
+use crate::process::{Channel, Explorer, Process};
+
+const EPSILON: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 100_000;
+const MAX_POISSON_MEAN: f64 = 100.0;
+
+#[derive(Clone, Debug)]
+pub struct Ctmc {
+    states: Vec<Process>,
+    init: Vec<(usize, f64)>,
+    transitions: Vec<Vec<(Channel, usize, f64)>>,
+}
+impl Ctmc {
+    pub fn new(ctx: &Context, init: Process) -> Self {
+        let explorer = Explorer::new(ctx, init.clone());
+        let mut ctmc = Self {
+            states: vec![],
+            init: vec![],
+            transitions: vec![],
+        };
+        let mut index = HashMap::new();
+        let mut id_of = |p: Process, ctmc: &mut Self| {
+            *index.entry(p.clone()).or_insert_with(|| {
+                ctmc.states.push(p);
+                ctmc.transitions.push(vec![]);
+                ctmc.states.len() - 1
+            })
+        };
+
+        ctmc.init = init
+            .normalize()
+            .distribution(ctx)
+            .into_iter()
+            .map(|(v, p)| (id_of(p, &mut ctmc), v))
+            .collect();
+        let mut next = 0;
+        while next < ctmc.states.len() {
+            let s = next;
+            next += 1;
+            for (a, rate, q) in explorer.rates(&ctmc.states[s].clone()) {
+                for (v, p) in q.distribution(ctx) {
+                    let t = id_of(p, &mut ctmc);
+                    match ctmc.transitions[s]
+                        .iter_mut()
+                        .find(|(b, u, _)| *b == a && *u == t)
+                    {
+                        Some((_, _, r)) => *r += rate * v,
+                        None => ctmc.transitions[s].push((a.clone(), t, rate * v)),
+                    }
+                }
+            }
+        }
+        ctmc
+    }
+    pub fn states(&self) -> &[Process] {
+        &self.states
+    }
+    pub fn transitions(&self, s: usize) -> &[(Channel, usize, f64)] {
+        &self.transitions[s]
+    }
+    pub fn initial(&self) -> Vec<f64> {
+        let mut dist = vec![0.0; self.states.len()];
+        for (s, v) in &self.init {
+            dist[*s] += v;
+        }
+        dist
+    }
+    pub fn exit_rate(&self, s: usize) -> f64 {
+        self.rates(s).values().sum()
+    }
+    pub fn rates(&self, s: usize) -> BTreeMap<usize, f64> {
+        let mut rates = BTreeMap::new();
+        for (_, t, r) in &self.transitions[s] {
+            if *t != s {
+                *rates.entry(*t).or_insert(0.0) += r;
+            }
+        }
+        rates
+    }
+    pub fn enabling(&self, ch: &Channel) -> HashSet<usize> {
+        (0..self.states.len())
+            .filter(|s| self.transitions[*s].iter().any(|(a, ..)| a == ch))
+            .collect()
+    }
+    pub fn probability(&self, dist: &[f64], states: &HashSet<usize>) -> f64 {
+        states.iter().map(|s| dist[*s]).sum()
+    }
+    pub fn throughput(&self, dist: &[f64], ch: &Channel) -> f64 {
+        self.transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(s, ts)| ts.iter().map(move |t| (s, t)))
+            .filter(|(_, (a, ..))| a == ch)
+            .map(|(s, (_, _, r))| dist[s] * r)
+            .sum()
+    }
+    pub fn steady_state(&self) -> Vec<f64> {
+        let lambda = self.uniformisation_rate() * 1.1;
+        let mut dist = self.initial();
+        if lambda == 0.0 {
+            return dist;
+        }
+        for _ in 0..MAX_ITERATIONS {
+            let next = self.uniformised_step(&dist, lambda);
+            let delta = next
+                .iter()
+                .zip(&dist)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            dist = next;
+            if delta < EPSILON {
+                break;
+            }
+        }
+        dist
+    }
+    pub fn transient(&self, time: f64) -> Vec<f64> {
+        let lambda = self.uniformisation_rate();
+        let mut dist = self.initial();
+        if lambda == 0.0 || time <= 0.0 {
+            return dist;
+        }
+        let steps = (lambda * time / MAX_POISSON_MEAN).ceil() as usize;
+        let mean = lambda * time / steps as f64;
+        for _ in 0..steps {
+            let mut term = dist;
+            let mut weight = (-mean).exp();
+            let mut total = weight;
+            dist = term.iter().map(|v| v * weight).collect();
+            let mut k = 0;
+            while total < 1.0 - EPSILON && k < MAX_ITERATIONS {
+                k += 1;
+                term = self.uniformised_step(&term, lambda);
+                weight *= mean / k as f64;
+                total += weight;
+                for (d, v) in dist.iter_mut().zip(&term) {
+                    *d += weight * v;
+                }
+            }
+        }
+        dist
+    }
+    fn uniformisation_rate(&self) -> f64 {
+        (0..self.states.len())
+            .map(|s| self.exit_rate(s))
+            .fold(0.0, f64::max)
+    }
+    fn uniformised_step(&self, dist: &[f64], lambda: f64) -> Vec<f64> {
+        let mut next = dist.to_vec();
+        for (s, v) in dist.iter().enumerate() {
+            for (t, r) in self.rates(s) {
+                next[s] -= v * r / lambda;
+                next[t] += v * r / lambda;
+            }
+        }
+        next
+    }
+
+    pub fn to_prism_tra(&self) -> String {
+        let rates = (0..self.states.len()).map(|s| self.rates(s)).collect_vec();
+        let transitions = rates.iter().map(BTreeMap::len).sum::<usize>();
+        let lines = rates
+            .iter()
+            .enumerate()
+            .flat_map(|(s, rates)| rates.iter().map(move |(t, r)| format!("{s} {t} {r}\n")));
+        [format!("{} {}\n", self.states.len(), transitions)]
+            .into_iter()
+            .chain(lines)
+            .collect()
+    }
+    pub fn to_matrix_market(&self) -> String {
+        let entries = (0..self.states.len())
+            .flat_map(|s| {
+                let rates = self.rates(s);
+                let exit = rates.values().sum::<f64>();
+                [(s, -exit)]
+                    .into_iter()
+                    .filter(|(_, r)| *r != 0.0)
+                    .chain(rates)
+                    .map(move |(t, r)| format!("{} {} {r}\n", s + 1, t + 1))
+            })
+            .collect_vec();
+        let n = self.states.len();
+        [
+            "%%MatrixMarket matrix coordinate real general\n".to_string(),
+            format!("{n} {n} {}\n", entries.len()),
+        ]
+        .into_iter()
+        .chain(entries)
+        .collect()
+    }
+}
@@ -0,0 +1,188 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+
+use itertools::Itertools;
+use lalrpop_util::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+    pub fn cover(self, other: Self) -> Self {
+        Self::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+// the source position of a process node, ignored when nodes are compared so
+// that parsed processes stay equal to the ones built by hand
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Loc(pub Option<Span>);
+impl Loc {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self(Some(Span::new(start, end)))
+    }
+    pub fn span(self) -> Option<Span> {
+        self.0
+    }
+}
+impl PartialEq for Loc {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+impl Eq for Loc {}
+impl Hash for Loc {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+impl PartialOrd for Loc {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Loc {
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+}
+impl<T: Display> Display for Spanned<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.node)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Span>,
+    pub hint: Option<String>,
+}
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Option<Span>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            hint: None,
+        }
+    }
+    pub fn warning(message: impl Into<String>, span: Option<Span>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(message, span)
+        }
+    }
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+    pub fn from_parse_error(e: &ParseError<usize, String, &'static str>) -> Self {
+        match e {
+            ParseError::InvalidToken { location } => {
+                Self::error("invalid token", Some(Span::new(*location, *location + 1)))
+            }
+            ParseError::UnrecognizedEof { location, expected } => Self::error(
+                "unexpected end of file",
+                Some(Span::new(*location, *location)),
+            )
+            .with_hint(expected_one_of(expected)),
+            ParseError::UnrecognizedToken {
+                token: (l, t, r),
+                expected,
+            } => Self::error(format!("unexpected `{t}`"), Some(Span::new(*l, *r)))
+                .with_hint(expected_one_of(expected)),
+            ParseError::ExtraToken { token: (l, t, r) } => Self::error(
+                format!("unexpected `{t}` after the end of the program"),
+                Some(Span::new(*l, *r)),
+            ),
+            ParseError::User { error } => Self::error(*error, None),
+        }
+    }
+    pub fn render(&self, source: &str, file: &str) -> String {
+        let mut out = format!("{}: {}\n", self.severity, self.message);
+        match self.span {
+            Some(span) => {
+                let (line, col) = line_col(source, span.start);
+                let text = source.lines().nth(line - 1).unwrap_or_default();
+                let width = line.to_string().len();
+                let pad = " ".repeat(width);
+                let len = span
+                    .end
+                    .min(span.start + text.len() + 1 - col)
+                    .saturating_sub(span.start)
+                    .max(1);
+                out += &format!("{pad}--> {file}:{line}:{col}\n");
+                out += &format!("{pad} |\n");
+                out += &format!("{line} | {text}\n");
+                out += &format!("{pad} | {}{}\n", " ".repeat(col - 1), "^".repeat(len));
+            }
+            None => out += &format!(" --> {file}\n"),
+        }
+        if let Some(hint) = &self.hint {
+            out += &format!(" = {hint}\n");
+        }
+        out
+    }
+}
+
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, col)
+}
+
+fn expected_one_of(expected: &[String]) -> String {
+    let names = expected.iter().map(|e| describe(e)).unique().collect_vec();
+    match names.as_slice() {
+        [] => "nothing else was expected here".to_string(),
+        [name] => format!("expected {name}"),
+        [init @ .., last] => format!("expected one of {} or {last}", init.join(", ")),
+    }
+}
+
+fn describe(terminal: &str) -> String {
+    match terminal {
+        r##"r#"[a-zA-Z_][a-zA-Z0-9_]*"#"## => "a name".to_string(),
+        r##"r#"[0-9]+"#"## => "a number".to_string(),
+        r##"r#"[0-9]+\\.[0-9]+"#"## => "a decimal number".to_string(),
+        _ => format!(
+            "`{}`",
+            terminal
+                .trim_matches('"')
+                .replace("\\\"", "\"")
+                .replace("\\\\", "\\")
+        ),
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+use lalrpop_util::lexer::Token;
+use lalrpop_util::ParseError;
+
+use crate::diagnostic::{Diagnostic, Span};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Parse(ParseError<usize, String, &'static str>),
+    UndefinedConstant(String),
+    UndefinedMain(String),
+    MissingMain,
+    DuplicateConstant(String),
+    UnguardedRecursion(String),
+    InvalidWeights,
+    UnexpectedName(String),
+    NumberTooLarge(String),
+    UnexpectedWord(String, Vec<&'static str>),
+    Located(Box<Error>, Span),
+}
+impl Error {
+    // errors keep the innermost position they are given
+    pub fn at(self, span: Option<Span>) -> Self {
+        match (self, span) {
+            (e @ Error::Located(..), _) | (e, None) => e,
+            (e, Some(span)) => Error::Located(Box::new(e), span),
+        }
+    }
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::Located(_, span) => Some(*span),
+            _ => None,
+        }
+    }
+    pub fn diagnostic(&self) -> Diagnostic {
+        let d = Diagnostic::error(self.to_string(), None);
+        match self {
+            Error::Parse(e) => Diagnostic::from_parse_error(e),
+            Error::Located(e, span) => Diagnostic {
+                span: Some(*span),
+                ..e.diagnostic()
+            },
+            Error::UndefinedMain(_) | Error::MissingMain => {
+                d.with_hint("declare the main process with #![start(Name)]")
+            }
+            Error::UnguardedRecursion(_) => d.with_hint("prefix the recursive call with an action"),
+            Error::InvalidWeights => d.with_hint("give at least one branch a positive weight"),
+            Error::UnexpectedName(name) => {
+                d.with_hint(format!("call the constant with \"{name}()\""))
+            }
+            Error::NumberTooLarge(_) => d.with_hint(format!("numbers go up to {}", u32::MAX)),
+            Error::UndefinedConstant(_)
+            | Error::DuplicateConstant(_)
+            | Error::UnexpectedWord(..) => d,
+        }
+    }
+}
+// keywords are only matched where the grammar expects them, so that models can still
+// use these words as names for constants and channels
+pub fn keyword<T, E: From<Error>>(
+    word: String,
+    expected: &[&'static str],
+    span: Span,
+) -> std::result::Result<String, ParseError<usize, T, E>> {
+    if expected.contains(&word.as_str()) {
+        return Ok(word);
+    }
+    let error = Error::UnexpectedWord(word, expected.to_vec()).at(Some(span));
+    Err(ParseError::User {
+        error: error.into(),
+    })
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "{e}"),
+            Error::UndefinedConstant(name) => write!(f, "constant \"{name}\" is not defined"),
+            Error::UndefinedMain(name) => write!(f, "main process \"{name}\" is not defined"),
+            Error::MissingMain => write!(f, "no main process is declared"),
+            Error::DuplicateConstant(name) => {
+                write!(f, "constant \"{name}\" is defined more than once")
+            }
+            Error::UnguardedRecursion(name) => {
+                write!(
+                    f,
+                    "constant \"{name}\" calls itself without a guarding action"
+                )
+            }
+            Error::InvalidWeights => write!(
+                f,
+                "probabilistic weights must be non-negative and not all zero"
+            ),
+            Error::UnexpectedName(name) => write!(f, "expected a process, found \"{name}\""),
+            Error::NumberTooLarge(n) => write!(f, "number {n} is too large"),
+            Error::UnexpectedWord(word, expected) => {
+                let expected = expected.iter().map(|k| format!("\"{k}\"")).join(" or ");
+                write!(f, "expected {expected}, found \"{word}\"")
+            }
+            Error::Located(e, _) => write!(f, "{e}"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl<'a> From<ParseError<usize, Token<'a>, Error>> for Error {
+    fn from(e: ParseError<usize, Token<'a>, Error>) -> Self {
+        match e {
+            ParseError::User { error } => error,
+            e => Error::Parse(
+                e.map_token(|t| t.1.to_string())
+                    .map_error(|_| unreachable!("user errors are returned as they are")),
+            ),
+        }
+    }
+}
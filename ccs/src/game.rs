@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use crate::transition_system::TransitionSystem;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Move<S, T, L> {
+    Left(L, S),
+    Right(L, T),
+}
+impl<S, T, L> Move<S, T, L> {
+    pub fn label(&self) -> &L {
+        match self {
+            Move::Left(l, _) | Move::Right(l, _) => l,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Counterexample<S, T, L> {
+    init: (S, T),
+    strategy: HashMap<(S, T), Move<S, T, L>>,
+}
+impl<S: Eq + Hash, T: Eq + Hash, L> Counterexample<S, T, L> {
+    pub fn init(&self) -> &(S, T) {
+        &self.init
+    }
+    pub fn attack(&self, p: S, q: T) -> Option<&Move<S, T, L>> {
+        self.strategy.get(&(p, q))
+    }
+}
+
+pub fn answers<T, U>(
+    t: &T,
+    u: &U,
+    (p, q): &(T::State, U::State),
+    m: &Move<T::State, U::State, T::Label>,
+) -> Vec<(T::State, U::State)>
+where
+    T: TransitionSystem,
+    U: TransitionSystem<Label = T::Label>,
+{
+    match m {
+        Move::Left(a, p2) => u
+            .successors(q)
+            .into_iter()
+            .filter(|(b, _)| a == b)
+            .map(|(_, q2)| (p2.clone(), q2))
+            .collect(),
+        Move::Right(b, q2) => t
+            .successors(p)
+            .into_iter()
+            .filter(|(a, _)| a == b)
+            .map(|(_, p2)| (p2, q2.clone()))
+            .collect(),
+    }
+}
+
+pub type Outcome<T, U> = Result<
+    (),
+    Counterexample<
+        <T as TransitionSystem>::State,
+        <U as TransitionSystem>::State,
+        <T as TransitionSystem>::Label,
+    >,
+>;
+
+pub fn bisimilar<T, U>(t: &T, u: &U) -> Outcome<T, U>
+where
+    T: TransitionSystem,
+    U: TransitionSystem<Label = T::Label>,
+{
+    let init = (t.initial(), u.initial());
+    let mut pending_moves: HashMap<_, Vec<_>> = HashMap::new();
+    let mut preds: HashMap<_, Vec<_>> = HashMap::new();
+    let mut strategy = HashMap::new();
+    let mut seen = HashSet::from([init.clone()]);
+    let mut queue = VecDeque::from([init.clone()]);
+
+    while let Some((p, q)) = queue.pop_front() {
+        let pnext = t.successors(&p);
+        let qnext = u.successors(&q);
+        let left = pnext.iter().map(|(a, p2)| {
+            let answers = qnext
+                .iter()
+                .filter(|(b, _)| a == b)
+                .map(|(_, q2)| (p2.clone(), q2.clone()))
+                .collect::<Vec<_>>();
+            (Move::Left(a.clone(), p2.clone()), answers)
+        });
+        let right = qnext.iter().map(|(b, q2)| {
+            let answers = pnext
+                .iter()
+                .filter(|(a, _)| a == b)
+                .map(|(_, p2)| (p2.clone(), q2.clone()))
+                .collect::<Vec<_>>();
+            (Move::Right(b.clone(), q2.clone()), answers)
+        });
+
+        let mut moves = vec![];
+        let mut lost = vec![];
+        for (i, (m, answers)) in left.chain(right).enumerate() {
+            let mut pending = 0;
+            for answer in answers {
+                if strategy.contains_key(&answer) {
+                    continue;
+                }
+                pending += 1;
+                preds
+                    .entry(answer.clone())
+                    .or_default()
+                    .push(((p.clone(), q.clone()), i));
+                if seen.insert(answer.clone()) {
+                    queue.push_back(answer);
+                }
+            }
+            if pending == 0 {
+                lost.push(m.clone());
+            }
+            moves.push((m, pending));
+        }
+        pending_moves.insert((p.clone(), q.clone()), moves);
+
+        if let Some(m) = lost.into_iter().next() {
+            let mut worklist = vec![((p, q), m)];
+            while let Some((pair, m)) = worklist.pop() {
+                if strategy.contains_key(&pair) {
+                    continue;
+                }
+                strategy.insert(pair.clone(), m);
+                for (pred, i) in preds.remove(&pair).unwrap_or_default() {
+                    if strategy.contains_key(&pred) {
+                        continue;
+                    }
+                    let (m, pending) = &mut pending_moves.get_mut(&pred).unwrap()[i];
+                    *pending -= 1;
+                    if *pending == 0 {
+                        worklist.push((pred, m.clone()));
+                    }
+                }
+            }
+            if strategy.contains_key(&init) {
+                return Err(Counterexample { init, strategy });
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,16 @@
 pub mod ast;
+pub mod bisimulation;
+pub mod check;
 pub mod context;
+pub mod cost;
+pub mod ctmc;
+pub mod diagnostic;
+pub mod error;
+pub mod game;
 pub mod lts;
+pub mod model_check;
 pub mod process;
+pub mod pts;
+pub mod rewrite;
+pub mod transition_system;
 pub mod utils;
@@ -1,32 +1,43 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 
 use itertools::Itertools;
 
-use super::process::{Channel, Process};
+use super::process::{Channel, Process, Substitution};
+use crate::bisimulation::{bisimilarity, Bisimulation};
 use crate::context::Context;
+use crate::cost::{self, MaxCost};
+use crate::model_check::{self, Formula};
+use crate::transition_system::TransitionSystem;
 
 pub type Transition = (Process, Channel, Process);
-pub type Bisimulation<'a, 'b> = HashSet<(&'a Process, &'b Process)>;
 
 #[derive(Clone, Debug, PartialEq)]
-pub struct Lts(HashSet<Transition>);
+pub struct Lts {
+    init: Process,
+    transitions: HashSet<Transition>,
+}
 impl Lts {
-    pub fn new(ts: HashSet<Transition>) -> Self {
-        Self(ts)
+    pub fn new(init: Process, ts: HashSet<Transition>) -> Self {
+        Self {
+            init,
+            transitions: ts,
+        }
     }
-    pub fn from_array<const N: usize>(t: [(&Process, &Channel, &Process); N]) -> Self {
-        Self(
+    pub fn from_array<const N: usize>(t: [(&Process, &Channel, &Process); N]) -> Option<Self> {
+        let init = t.first()?.0.clone();
+        Some(Self::new(
+            init,
             t.into_iter()
                 .map(|t| (t.0.clone(), t.1.clone(), t.2.clone()))
                 .collect(),
-        )
+        ))
     }
     pub fn weaken(self) -> Self {
-        let mut transitions = self.0.clone();
+        let mut transitions = self.transitions.clone();
         for s in self.nodes() {
             transitions.insert((s.clone(), Channel::Tau, s.clone()));
         }
-        for (p, _, q) in self.0.iter().filter(|t| t.1.is_tau()) {
+        for (p, _, q) in self.transitions.iter().filter(|t| t.1.is_tau()) {
             for before in self.transitions_to(p) {
                 transitions.insert((before.0.clone(), before.1.clone(), q.clone()));
             }
@@ -34,68 +45,197 @@ impl Lts {
                 transitions.insert((p.clone(), after.1.clone(), after.2.clone()));
             }
         }
-        Self(transitions)
+        Self::new(self.init, transitions)
     }
     pub fn flatten(self) -> Self {
-        Self(
-            self.0
+        Self::new(
+            self.init.flatten(),
+            self.transitions
                 .into_iter()
                 .map(|t| (t.0.flatten(), t.1, t.2.flatten()))
                 .collect(),
         )
     }
     pub fn symbolic(self, ctx: &Context) -> Self {
-        Self(
-            self.0
+        Self::new(
+            self.init.fold_consts(ctx),
+            self.transitions
                 .into_iter()
                 .map(|t| (t.0.fold_consts(ctx), t.1, t.2.fold_consts(ctx)))
                 .collect(),
         )
     }
+
+    pub fn par(&self, other: &Lts) -> Self {
+        explore(Process::par(self.init.clone(), other.init.clone()), |s| {
+            let Process::Par(p, q) = s else {
+                unreachable!()
+            };
+            let ptrans = self.transitions_from(p).into_iter().collect_vec();
+            let qtrans = other.transitions_from(q).into_iter().collect_vec();
+            let mut next = vec![];
+            // transitions that synchronise are not interleaved
+            let mut psynched = vec![false; ptrans.len()];
+            let mut qsynched = vec![false; qtrans.len()];
+            for (i, (_, a, p2)) in ptrans.iter().enumerate() {
+                for (j, (_, b, q2)) in qtrans.iter().enumerate() {
+                    if a.is_synched_with(b) {
+                        next.push((Channel::tau(), Process::par(p2.clone(), q2.clone())));
+                        psynched[i] = true;
+                        qsynched[j] = true;
+                    }
+                }
+            }
+            for ((_, a, p2), _) in ptrans
+                .iter()
+                .zip(&psynched)
+                .filter(|(t, s)| !t.1.is_tick() && !**s)
+            {
+                next.push((a.clone(), Process::par(p2.clone(), *q.clone())));
+            }
+            for ((_, b, q2), _) in qtrans
+                .iter()
+                .zip(&qsynched)
+                .filter(|(t, s)| !t.1.is_tick() && !**s)
+            {
+                next.push((b.clone(), Process::par(*p.clone(), q2.clone())));
+            }
+            if next.iter().all(|(a, _)| !a.is_tau()) {
+                let pticks = ptrans.iter().filter(|t| t.1.is_tick());
+                let qticks = qtrans.iter().filter(|t| t.1.is_tick()).collect_vec();
+                for ((_, _, p2), (_, _, q2)) in pticks.cartesian_product(qticks) {
+                    next.push((Channel::Tick, Process::par(p2.clone(), q2.clone())));
+                }
+            }
+            next
+        })
+    }
+    pub fn restrict(&self, chans: &[String]) -> Self {
+        explore(
+            Process::restriction(self.init.clone(), chans.to_vec()),
+            |s| {
+                let Process::Restriction(p, _, _) = s else {
+                    unreachable!()
+                };
+                self.transitions_from(p)
+                    .into_iter()
+                    .filter(|t| t.1.is_tick() || !chans.iter().any(|ch| ch == t.1.name()))
+                    .map(|t| {
+                        (
+                            t.1.clone(),
+                            Process::restriction(t.2.clone(), chans.to_vec()),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+    pub fn rename(&self, subs: &Substitution) -> Self {
+        explore(
+            Process::substitution(self.init.clone(), subs.clone()),
+            |s| {
+                let Process::Substitution(p, _) = s else {
+                    unreachable!()
+                };
+                self.transitions_from(p)
+                    .into_iter()
+                    .map(|t| {
+                        (
+                            subs.replace_channel(t.1.clone()),
+                            Process::substitution(t.2.clone(), subs.clone()),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+    pub fn hide(&self, chans: &[String]) -> Self {
+        explore(Process::hiding(self.init.clone(), chans.to_vec()), |s| {
+            let Process::Hiding(p, _) = s else {
+                unreachable!()
+            };
+            self.transitions_from(p)
+                .into_iter()
+                .map(|t| {
+                    let ch = if !t.1.is_tick() && chans.iter().any(|ch| ch == t.1.name()) {
+                        Channel::tau()
+                    } else {
+                        t.1.clone()
+                    };
+                    (ch, Process::hiding(t.2.clone(), chans.to_vec()))
+                })
+                .collect()
+        })
+    }
+
+    pub fn init(&self) -> &Process {
+        &self.init
+    }
     pub fn edges(&self) -> HashSet<&Channel> {
-        self.0.iter().map(|t| &t.1).collect()
+        self.transitions.iter().map(|t| &t.1).collect()
     }
     pub fn nodes(&self) -> HashSet<&Process> {
-        self.0.iter().flat_map(|t| [&t.0, &t.2]).collect()
+        self.transitions.iter().flat_map(|t| [&t.0, &t.2]).collect()
+    }
+    pub fn deadlocks(&self, ctx: &Context) -> HashSet<&Process> {
+        self.nodes()
+            .into_iter()
+            .chain([&self.init])
+            .filter(|p| self.transitions.iter().all(|t| t.0 != **p))
+            .filter(|p| !(*p).clone().unfold_consts(ctx).is_ok_and(|p| p.is_done()))
+            .collect()
     }
     pub fn transitions(&self) -> &HashSet<Transition> {
-        &self.0
+        &self.transitions
     }
     pub fn transitions_from(&self, p: &Process) -> HashSet<&Transition> {
-        self.0.iter().filter(|t| t.0 == *p).collect()
+        self.transitions.iter().filter(|t| t.0 == *p).collect()
     }
     pub fn transitions_to(&self, p: &Process) -> HashSet<&Transition> {
-        self.0.iter().filter(|t| t.2 == *p).collect()
+        self.transitions.iter().filter(|t| t.2 == *p).collect()
+    }
+    pub fn bisimilarity(&self, other: &Lts) -> Bisimulation<Process, Process> {
+        bisimilarity(self, other)
+    }
+    pub fn satisfies(&self, f: &Formula<Channel>) -> bool {
+        model_check::satisfies(self, f)
+    }
+    pub fn min_cost(&self, ctx: &Context, target: &Channel) -> Option<(u32, Vec<Transition>)> {
+        cost::min_cost(self, |ch| ctx.cost_of(ch), target)
+    }
+    pub fn max_cost(&self, ctx: &Context, target: &Channel) -> MaxCost<Transition> {
+        cost::max_cost(self, |ch| ctx.cost_of(ch), target)
     }
-    pub fn bisimilarity<'a, 'b>(&'a self, other: &'b Lts) -> Bisimulation<'a, 'b> {
-        let top = self
-            .nodes()
+    pub fn positive_cycles(&self, ctx: &Context) -> Vec<Vec<Process>> {
+        cost::positive_cycles(self, |ch| ctx.cost_of(ch))
+    }
+}
+impl TransitionSystem for Lts {
+    type State = Process;
+    type Label = Channel;
+
+    fn initial(&self) -> Process {
+        self.init.clone()
+    }
+    fn successors(&self, s: &Process) -> Vec<(Channel, Process)> {
+        self.transitions_from(s)
             .into_iter()
-            .cartesian_product(other.nodes().iter().copied())
-            .collect();
-        let f = |r: &Bisimulation<'a, 'b>| {
-            let is_similar = |this: &Lts, other: &Lts, p: &Process, q: &Process| {
-                this.transitions_from(p).iter().all(|pt| {
-                    other.transitions_from(q).iter().any(|qt| {
-                        pt.1 == qt.1 && (r.contains(&(&pt.2, &qt.2)) || r.contains(&(&qt.2, &pt.2)))
-                    })
-                })
-            };
-            r.iter()
-                .filter(|(p, q)| is_similar(self, other, p, q) && is_similar(other, self, q, p))
-                .cloned()
-                .collect()
-        };
-        gfp(f, top)
+            .map(|t| (t.1.clone(), t.2.clone()))
+            .collect()
     }
 }
 
-fn gfp<R: PartialEq>(f: impl Fn(&R) -> R, mut curr: R) -> R {
-    loop {
-        let new = f(&curr);
-        if new == curr {
-            return curr;
+fn explore(init: Process, next: impl Fn(&Process) -> Vec<(Channel, Process)>) -> Lts {
+    let mut transitions = HashSet::new();
+    let mut seen = HashSet::from([init.clone()]);
+    let mut queue = VecDeque::from([init.clone()]);
+    while let Some(p) = queue.pop_front() {
+        for (a, q) in next(&p) {
+            if seen.insert(q.clone()) {
+                queue.push_back(q.clone());
+            }
+            transitions.insert((p.clone(), a, q));
         }
-        curr = new;
     }
+    Lts::new(init, transitions)
 }
@@ -0,0 +1,144 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use itertools::Itertools;
+
+use crate::transition_system::{reachable, TransitionSystem};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Formula<L> {
+    True,
+    False,
+    Not(Box<Formula<L>>),
+    And(Box<Formula<L>>, Box<Formula<L>>),
+    Or(Box<Formula<L>>, Box<Formula<L>>),
+    // `None` stands for any label
+    Diamond(Option<L>, Box<Formula<L>>),
+    Box(Option<L>, Box<Formula<L>>),
+    Possibly(Box<Formula<L>>),
+    Always(Box<Formula<L>>),
+    Inevitably(Box<Formula<L>>),
+    Forever(Box<Formula<L>>),
+}
+impl<L> Formula<L> {
+    pub fn negate(f: Self) -> Self {
+        Self::Not(Box::new(f))
+    }
+    pub fn and(f: Self, g: Self) -> Self {
+        Self::And(Box::new(f), Box::new(g))
+    }
+    pub fn or(f: Self, g: Self) -> Self {
+        Self::Or(Box::new(f), Box::new(g))
+    }
+    pub fn diamond(l: Option<L>, f: Self) -> Self {
+        Self::Diamond(l, Box::new(f))
+    }
+    pub fn boxed(l: Option<L>, f: Self) -> Self {
+        Self::Box(l, Box::new(f))
+    }
+    pub fn possibly(f: Self) -> Self {
+        Self::Possibly(Box::new(f))
+    }
+    pub fn always(f: Self) -> Self {
+        Self::Always(Box::new(f))
+    }
+    pub fn inevitably(f: Self) -> Self {
+        Self::Inevitably(Box::new(f))
+    }
+    pub fn forever(f: Self) -> Self {
+        Self::Forever(Box::new(f))
+    }
+    pub fn deadlock() -> Self {
+        Self::boxed(None, Self::False)
+    }
+}
+
+pub fn satisfies<T: TransitionSystem>(ts: &T, f: &Formula<T::Label>) -> bool {
+    check(ts, f).contains(&ts.initial())
+}
+
+pub fn check<T: TransitionSystem>(ts: &T, f: &Formula<T::Label>) -> HashSet<T::State> {
+    let states = reachable(ts).into_iter().collect_vec();
+    let index = states
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.clone(), i))
+        .collect::<HashMap<_, _>>();
+    let edges = states
+        .iter()
+        .map(|s| {
+            ts.successors(s)
+                .into_iter()
+                .map(|(l, t)| (l, index[&t]))
+                .collect_vec()
+        })
+        .collect_vec();
+    sat(&edges, f)
+        .into_iter()
+        .map(|s| states[s].clone())
+        .collect()
+}
+
+fn sat<L: Clone + Eq + Hash>(edges: &[Vec<(L, usize)>], f: &Formula<L>) -> HashSet<usize> {
+    let all = || (0..edges.len()).collect::<HashSet<_>>();
+    let matches = |l: &Option<L>, a: &L| l.as_ref().is_none_or(|l| l == a);
+    match f {
+        Formula::True => all(),
+        Formula::False => HashSet::new(),
+        Formula::Not(f) => &all() - &sat(edges, f),
+        Formula::And(f, g) => &sat(edges, f) & &sat(edges, g),
+        Formula::Or(f, g) => &sat(edges, f) | &sat(edges, g),
+        Formula::Diamond(l, f) => {
+            let target = sat(edges, f);
+            all()
+                .into_iter()
+                .filter(|s| {
+                    edges[*s]
+                        .iter()
+                        .any(|(a, t)| matches(l, a) && target.contains(t))
+                })
+                .collect()
+        }
+        Formula::Box(l, f) => {
+            let target = sat(edges, f);
+            all()
+                .into_iter()
+                .filter(|s| {
+                    edges[*s]
+                        .iter()
+                        .all(|(a, t)| !matches(l, a) || target.contains(t))
+                })
+                .collect()
+        }
+        Formula::Possibly(f) => least_fixpoint(edges, sat(edges, f), |succ, set| {
+            succ.iter().any(|(_, t)| set.contains(t))
+        }),
+        Formula::Inevitably(f) => least_fixpoint(edges, sat(edges, f), |succ, set| {
+            !succ.is_empty() && succ.iter().all(|(_, t)| set.contains(t))
+        }),
+        Formula::Always(f) => {
+            let not = Formula::possibly(Formula::Not(f.clone()));
+            &all() - &sat(edges, &not)
+        }
+        Formula::Forever(f) => {
+            let not = Formula::inevitably(Formula::Not(f.clone()));
+            &all() - &sat(edges, &not)
+        }
+    }
+}
+
+fn least_fixpoint<L>(
+    edges: &[Vec<(L, usize)>],
+    mut set: HashSet<usize>,
+    step: impl Fn(&[(L, usize)], &HashSet<usize>) -> bool,
+) -> HashSet<usize> {
+    loop {
+        let next = (0..edges.len())
+            .filter(|s| !set.contains(s) && step(&edges[*s], &set))
+            .collect_vec();
+        if next.is_empty() {
+            return set;
+        }
+        set.extend(next);
+    }
+}
@@ -1,38 +1,108 @@
 use std::collections::HashSet;
 use std::fmt::Display;
 use std::hash::Hash;
+use std::ops::Mul;
 
 use itertools::Itertools;
 
 use super::context::Context;
 use super::lts::Transition;
+use crate::diagnostic::{Loc, Span};
+use crate::error::{Error, Result};
 use crate::lts::Lts;
+use crate::transition_system::TransitionSystem;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+type RatedTransition = (Process, Channel, Process, Weight);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Process {
-    Constant(String),
-    Action(Channel, Box<Process>),
+    Constant(String, Loc),
+    Action(Channel, Box<Process>, Loc),
+    // plain actions have rate 1.0, and a synchronisation fires at the product of its rates
+    Rated(Channel, Weight, Box<Process>, Loc),
     Sum(Vec<Process>),
     Par(Box<Process>, Box<Process>),
+    Sync(Box<Process>, Vec<String>, Box<Process>),
     Substitution(Box<Process>, Substitution),
-    Restriction(Box<Process>, Vec<String>),
+    Restriction(Box<Process>, Vec<String>, Loc),
+    Hiding(Box<Process>, Vec<String>),
+    Buffered(Box<Process>, String, Vec<String>),
+    Seq(Box<Process>, Box<Process>),
+    Skip,
+    Disrupt(Box<Process>, Box<Process>),
+    Rec(String, Box<Process>),
+    Replication(Box<Process>, Vec<Process>),
+    Delay(u32, Box<Process>),
+    Prob(Vec<(Weight, Process)>),
 }
 impl Process {
     pub fn is_nil(&self) -> bool {
         match self {
             Process::Sum(sum) => sum.is_empty(),
-            Process::Par(p, q) => p.is_nil() && q.is_nil(),
+            Process::Par(p, q) | Process::Sync(p, _, q) | Process::Disrupt(p, q) => {
+                p.is_nil() && q.is_nil()
+            }
+            Process::Seq(p, _) | Process::Rec(_, p) => p.is_nil(),
+            Process::Replication(p, copies) => p.is_nil() && copies.iter().all(Process::is_nil),
+            Process::Prob(branches) => branches.iter().all(|(_, p)| p.is_nil()),
+            _ => false,
+        }
+    }
+    pub fn is_done(&self) -> bool {
+        match self {
+            Process::Skip => true,
+            Process::Sum(sum) => sum.iter().any(Process::is_done),
+            Process::Par(p, q) | Process::Sync(p, _, q) | Process::Seq(p, q) => {
+                p.is_done() && q.is_done()
+            }
+            Process::Substitution(p, _)
+            | Process::Restriction(p, _, _)
+            | Process::Hiding(p, _)
+            | Process::Buffered(p, ..)
+            | Process::Disrupt(p, _)
+            | Process::Rec(_, p) => p.is_done(),
+            Process::Replication(p, copies) => p.is_done() && copies.iter().all(Process::is_done),
+            Process::Prob(branches) => branches.iter().all(|(_, p)| p.is_done()),
             _ => false,
         }
     }
+    // false only when no run can reach a state that is_done; calls are assumed to terminate
+    pub fn may_terminate(&self) -> bool {
+        match self {
+            Process::Skip | Process::Constant(..) => true,
+            Process::Sum(sum) => sum.iter().any(Process::may_terminate),
+            Process::Prob(branches) => branches.iter().any(|(_, p)| p.may_terminate()),
+            Process::Par(p, q) | Process::Sync(p, _, q) | Process::Seq(p, q) => {
+                p.may_terminate() && q.may_terminate()
+            }
+            Process::Disrupt(p, q) => p.may_terminate() || q.may_terminate(),
+            Process::Replication(p, copies) => {
+                p.may_terminate() && copies.iter().all(Process::may_terminate)
+            }
+            Process::Action(_, p, _)
+            | Process::Rated(_, _, p, _)
+            | Process::Delay(_, p)
+            | Process::Substitution(p, _)
+            | Process::Restriction(p, _, _)
+            | Process::Hiding(p, _)
+            | Process::Buffered(p, ..)
+            | Process::Rec(_, p) => p.may_terminate(),
+        }
+    }
     pub fn nil() -> Self {
         Process::Sum(vec![])
     }
+    pub fn skip() -> Self {
+        Process::Skip
+    }
     pub fn constant(s: impl Into<String>) -> Self {
-        Process::Constant(s.into())
+        Process::Constant(s.into(), Loc::default())
     }
     pub fn action(l: Channel, p: Self) -> Self {
-        Process::Action(l, Box::new(p))
+        Process::Action(l, Box::new(p), Loc::default())
+    }
+    pub fn rated(l: Channel, rate: Weight, p: Self) -> Self {
+        Process::Rated(l, rate, Box::new(p), Loc::default())
     }
     pub fn sum(sum: Vec<Self>) -> Self {
         Process::Sum(sum)
@@ -40,17 +110,296 @@ impl Process {
     pub fn par(p: Self, q: Self) -> Self {
         Process::Par(Box::new(p), Box::new(q))
     }
+    pub fn sync(p: Self, chans: Vec<String>, q: Self) -> Self {
+        Process::Sync(Box::new(p), chans, Box::new(q))
+    }
     pub fn substitution(p: Self, subs: Substitution) -> Self {
         Process::Substitution(Box::new(p), subs)
     }
     pub fn restriction(p: Self, chans: Vec<String>) -> Self {
-        Process::Restriction(Box::new(p), chans)
+        Process::Restriction(Box::new(p), chans, Loc::default())
+    }
+    pub fn hiding(p: Self, chans: Vec<String>) -> Self {
+        Process::Hiding(Box::new(p), chans)
+    }
+    pub fn seq(p: Self, q: Self) -> Self {
+        Process::Seq(Box::new(p), Box::new(q))
+    }
+    pub fn rec(x: impl Into<String>, p: Self) -> Self {
+        Process::Rec(x.into(), Box::new(p))
+    }
+    pub fn prob(branches: Vec<(Weight, Self)>) -> Result<Self> {
+        let valid = |w: &Weight| w.0.is_finite() && w.0 >= 0.0;
+        if !branches.iter().all(|(w, _)| valid(w)) || branches.iter().all(|(w, _)| w.0 == 0.0) {
+            return Err(Error::InvalidWeights);
+        }
+        Ok(Process::Prob(branches))
+    }
+    pub fn delay(n: u32, p: Self) -> Self {
+        Process::Delay(n, Box::new(p))
+    }
+    pub fn replication(p: Self, copies: Vec<Self>) -> Self {
+        Process::Replication(Box::new(p), copies)
+    }
+    pub fn free_vars(&self) -> HashSet<String> {
+        match self {
+            Self::Constant(x, _) => HashSet::from([x.clone()]),
+            Self::Skip => HashSet::new(),
+            Self::Action(_, p, _)
+            | Self::Rated(_, _, p, _)
+            | Self::Delay(_, p)
+            | Self::Substitution(p, _)
+            | Self::Restriction(p, _, _)
+            | Self::Hiding(p, _)
+            | Self::Buffered(p, ..) => p.free_vars(),
+            Self::Sum(sum) => sum.iter().flat_map(Self::free_vars).collect(),
+            Self::Prob(branches) => branches.iter().flat_map(|(_, p)| p.free_vars()).collect(),
+            Self::Par(p, q) | Self::Sync(p, _, q) | Self::Seq(p, q) | Self::Disrupt(p, q) => {
+                p.free_vars().union(&q.free_vars()).cloned().collect()
+            }
+            Self::Rec(x, p) => {
+                let mut vars = p.free_vars();
+                vars.remove(x);
+                vars
+            }
+            Self::Replication(p, copies) => {
+                let mut vars = p.free_vars();
+                vars.extend(copies.iter().flat_map(Self::free_vars));
+                vars
+            }
+        }
+    }
+    pub fn children(&self) -> Vec<&Process> {
+        match self {
+            Self::Constant(_, _) | Self::Skip => vec![],
+            Self::Action(_, p, _)
+            | Self::Rated(_, _, p, _)
+            | Self::Delay(_, p)
+            | Self::Substitution(p, _)
+            | Self::Restriction(p, _, _)
+            | Self::Hiding(p, _)
+            | Self::Buffered(p, ..)
+            | Self::Rec(_, p) => vec![p],
+            Self::Sum(sum) => sum.iter().collect(),
+            Self::Prob(branches) => branches.iter().map(|(_, p)| p).collect(),
+            Self::Par(p, q) | Self::Sync(p, _, q) | Self::Seq(p, q) | Self::Disrupt(p, q) => {
+                vec![p, q]
+            }
+            Self::Replication(p, copies) => [&**p].into_iter().chain(copies).collect(),
+        }
+    }
+    pub fn unguarded_consts(&self) -> HashSet<String> {
+        match self {
+            Self::Constant(x, _) => HashSet::from([x.clone()]),
+            Self::Skip | Self::Action(..) | Self::Rated(..) | Self::Delay(..) => HashSet::new(),
+            Self::Substitution(p, _)
+            | Self::Restriction(p, _, _)
+            | Self::Hiding(p, _)
+            | Self::Buffered(p, ..) => p.unguarded_consts(),
+            Self::Sum(sum) => sum.iter().flat_map(Self::unguarded_consts).collect(),
+            Self::Prob(branches) => branches
+                .iter()
+                .flat_map(|(_, p)| p.unguarded_consts())
+                .collect(),
+            Self::Seq(p, _) if !p.is_done() => p.unguarded_consts(),
+            Self::Par(p, q) | Self::Sync(p, _, q) | Self::Seq(p, q) | Self::Disrupt(p, q) => p
+                .unguarded_consts()
+                .union(&q.unguarded_consts())
+                .cloned()
+                .collect(),
+            Self::Rec(x, p) => {
+                let mut consts = p.unguarded_consts();
+                consts.remove(x);
+                consts
+            }
+            Self::Replication(p, copies) => {
+                let mut consts = p.unguarded_consts();
+                consts.extend(copies.iter().flat_map(Self::unguarded_consts));
+                consts
+            }
+        }
+    }
+    // rec variables whose body calls them before any action
+    pub fn unguarded_recs(&self) -> Vec<(String, Option<Span>)> {
+        let mut recs = self
+            .children()
+            .into_iter()
+            .flat_map(Self::unguarded_recs)
+            .collect_vec();
+        if let Self::Rec(x, p) = self {
+            if p.unguarded_consts().contains(x) {
+                recs.insert(0, (x.clone(), p.call_span(x)));
+            }
+        }
+        recs
+    }
+    // where x is first called, not counting rec bodies that bind it again
+    pub fn call_span(&self, x: &str) -> Option<Span> {
+        match self {
+            Self::Constant(y, loc) if y == x => loc.span(),
+            Self::Rec(y, _) if y == x => None,
+            _ => self.children().into_iter().find_map(|p| p.call_span(x)),
+        }
+    }
+    // the source this node was parsed from, as far as its prefixes and calls reach
+    pub fn span(&self) -> Option<Span> {
+        let own = match self {
+            Self::Constant(_, loc) | Self::Action(.., loc) | Self::Rated(.., loc) => loc.span(),
+            _ => None,
+        };
+        self.children()
+            .into_iter()
+            .filter_map(Self::span)
+            .chain(own)
+            .reduce(Span::cover)
+    }
+    pub fn replace_var(self, x: &str, q: &Process) -> Self {
+        match self {
+            Self::Constant(y, _) if y == x => q.clone(),
+            Self::Constant(_, _) | Self::Skip => self,
+            Self::Action(ch, p, _) => Self::action(ch, p.replace_var(x, q)),
+            Self::Rated(ch, r, p, _) => Self::rated(ch, r, p.replace_var(x, q)),
+            Self::Delay(n, p) => Self::delay(n, p.replace_var(x, q)),
+            Self::Sum(sum) => Self::sum(sum.into_iter().map(|p| p.replace_var(x, q)).collect()),
+            Self::Prob(branches) => Self::Prob(
+                branches
+                    .into_iter()
+                    .map(|(w, p)| (w, p.replace_var(x, q)))
+                    .collect(),
+            ),
+            Self::Par(p, r) => Self::par(p.replace_var(x, q), r.replace_var(x, q)),
+            Self::Sync(p, a, r) => Self::sync(p.replace_var(x, q), a, r.replace_var(x, q)),
+            Self::Seq(p, r) => Self::seq(p.replace_var(x, q), r.replace_var(x, q)),
+            Self::Disrupt(p, r) => Self::disrupt(p.replace_var(x, q), r.replace_var(x, q)),
+            Self::Substitution(p, s) => Self::substitution(p.replace_var(x, q), s),
+            Self::Restriction(p, r, _) => Self::restriction(p.replace_var(x, q), r),
+            Self::Hiding(p, h) => Self::hiding(p.replace_var(x, q), h),
+            Self::Buffered(p, c, b) => Self::buffered(p.replace_var(x, q), c, b),
+            Self::Replication(p, copies) => Self::replication(
+                p.replace_var(x, q),
+                copies.into_iter().map(|c| c.replace_var(x, q)).collect(),
+            ),
+            Self::Rec(y, p) if y == x => Self::Rec(y, p),
+            Self::Rec(y, p) => {
+                let free = q.free_vars();
+                if !free.contains(&y) {
+                    return Self::rec(y, p.replace_var(x, q));
+                }
+                let used = p.free_vars();
+                let fresh = (1..)
+                    .map(|i| format!("{y}{}", "'".repeat(i)))
+                    .find(|z| !free.contains(z) && !used.contains(z) && z != x)
+                    .unwrap();
+                let p = p.replace_var(&y, &Self::constant(&fresh));
+                Self::rec(fresh, p.replace_var(x, q))
+            }
+        }
+    }
+    pub fn close(self, ctx: &Context) -> Self {
+        fn close_rec(p: Process, ctx: &Context, bound: &mut Vec<String>) -> Process {
+            match p {
+                Process::Constant(ref name, _) if bound.contains(name) => p,
+                Process::Constant(name, loc) => match ctx.get_process(&name) {
+                    Some(body) => {
+                        bound.push(name.clone());
+                        let body = close_rec(body.clone(), ctx, bound);
+                        bound.pop();
+                        if body.free_vars().contains(&name) {
+                            Process::rec(name, body)
+                        } else {
+                            body
+                        }
+                    }
+                    None => Process::Constant(name, loc),
+                },
+                Process::Rec(x, p) => {
+                    bound.push(x.clone());
+                    let p = close_rec(*p, ctx, bound);
+                    bound.pop();
+                    Process::rec(x, p)
+                }
+                Process::Skip => p,
+                Process::Action(ch, p, _) => Process::action(ch, close_rec(*p, ctx, bound)),
+                Process::Rated(ch, r, p, _) => Process::rated(ch, r, close_rec(*p, ctx, bound)),
+                Process::Delay(n, p) => Process::delay(n, close_rec(*p, ctx, bound)),
+                Process::Prob(branches) => Process::Prob(
+                    branches
+                        .into_iter()
+                        .map(|(w, p)| (w, close_rec(p, ctx, bound)))
+                        .collect(),
+                ),
+                Process::Sum(sum) => {
+                    Process::sum(sum.into_iter().map(|p| close_rec(p, ctx, bound)).collect())
+                }
+                Process::Par(p, q) => {
+                    Process::par(close_rec(*p, ctx, bound), close_rec(*q, ctx, bound))
+                }
+                Process::Sync(p, a, q) => {
+                    Process::sync(close_rec(*p, ctx, bound), a, close_rec(*q, ctx, bound))
+                }
+                Process::Seq(p, q) => {
+                    Process::seq(close_rec(*p, ctx, bound), close_rec(*q, ctx, bound))
+                }
+                Process::Disrupt(p, q) => {
+                    Process::disrupt(close_rec(*p, ctx, bound), close_rec(*q, ctx, bound))
+                }
+                Process::Substitution(p, s) => Process::substitution(close_rec(*p, ctx, bound), s),
+                Process::Restriction(p, r, _) => Process::restriction(close_rec(*p, ctx, bound), r),
+                Process::Hiding(p, h) => Process::hiding(close_rec(*p, ctx, bound), h),
+                Process::Buffered(p, c, b) => Process::buffered(close_rec(*p, ctx, bound), c, b),
+                Process::Replication(p, copies) => Process::replication(
+                    close_rec(*p, ctx, bound),
+                    copies
+                        .into_iter()
+                        .map(|c| close_rec(c, ctx, bound))
+                        .collect(),
+                ),
+            }
+        }
+        close_rec(self, ctx, &mut vec![])
+    }
+    pub fn disrupt(p: Self, q: Self) -> Self {
+        Process::Disrupt(Box::new(p), Box::new(q))
+    }
+    pub fn buffered(p: Self, chan: String, contents: Vec<String>) -> Self {
+        Process::Buffered(Box::new(p), chan, contents)
+    }
+    pub fn with_buffers<'a>(
+        self,
+        ctx: &Context,
+        names: impl IntoIterator<Item = &'a String>,
+    ) -> Self {
+        let mut present = HashSet::new();
+        let mut p = &self;
+        while let Self::Buffered(inner, chan, _) = p {
+            present.insert(chan.as_str());
+            p = inner;
+        }
+        let missing = names
+            .into_iter()
+            .filter_map(|name| ctx.buffer_of(name))
+            .filter(|chan| !present.contains(chan))
+            .map(str::to_string)
+            .sorted()
+            .dedup()
+            .collect_vec();
+        missing
+            .into_iter()
+            .fold(self, |p, chan| Self::buffered(p, chan, vec![]))
     }
 
     pub fn flatten(self) -> Self {
         match self {
-            Self::Constant(_) => self,
-            Self::Action(ch, p) => Self::action(ch, p.flatten()),
+            Self::Constant(_, _) | Self::Skip => self,
+            Self::Action(ch, p, _) => Self::action(ch, p.flatten()),
+            Self::Rated(ch, r, p, _) => Self::rated(ch, r, p.flatten()),
+            Self::Delay(n, p) => Self::delay(n, p.flatten()),
+            Self::Prob(branches) => Self::Prob(
+                branches
+                    .into_iter()
+                    .map(|(w, p)| (w, p.flatten()))
+                    .collect(),
+            ),
             Self::Sum(sum) => {
                 let sum = sum
                     .into_iter()
@@ -76,50 +425,350 @@ impl Process {
                 }
                 Self::par(p, q)
             }
+            Self::Sync(p, chans, q) => Self::sync(p.flatten(), chans, q.flatten()),
             Self::Substitution(p, subs) => Self::substitution(p.flatten(), subs),
-            Self::Restriction(p, chans) => Self::restriction(p.flatten(), chans),
+            Self::Restriction(p, chans, _) => Self::restriction(p.flatten(), chans),
+            Self::Hiding(p, chans) => Self::hiding(p.flatten(), chans),
+            Self::Buffered(p, chan, contents) => Self::buffered(p.flatten(), chan, contents),
+            Self::Seq(p, q) => Self::seq(p.flatten(), q.flatten()),
+            Self::Disrupt(p, q) => Self::disrupt(p.flatten(), q.flatten()),
+            Self::Rec(x, p) => Self::rec(x, p.flatten()),
+            Self::Replication(p, copies) => {
+                Self::replication(p.flatten(), copies.into_iter().map(Self::flatten).collect())
+            }
+        }
+    }
+    pub fn normalize(self) -> Self {
+        match self {
+            Self::Constant(_, _) | Self::Skip => self,
+            Self::Action(ch, p, _) => Self::action(ch, p.normalize()),
+            Self::Rated(ch, r, p, _) => Self::rated(ch, r, p.normalize()),
+            Self::Delay(0, p) => p.normalize(),
+            Self::Prob(branches) => {
+                let mut merged: Vec<(Weight, Process)> = vec![];
+                for (w, p) in branches {
+                    let p = p.normalize();
+                    match merged.iter_mut().find(|(_, q)| *q == p) {
+                        Some((v, _)) => *v = Weight(v.0 + w.0),
+                        None => merged.push((w, p)),
+                    }
+                }
+                if merged.len() == 1 {
+                    return merged.remove(0).1;
+                }
+                merged.sort_by(|a, b| a.1.cmp(&b.1));
+                Self::Prob(merged)
+            }
+            Self::Delay(n, p) => Self::delay(n, p.normalize()),
+            Self::Sum(sum) => {
+                let sum = sum
+                    .into_iter()
+                    .flat_map(|p| match p.normalize() {
+                        Self::Sum(sum) => sum,
+                        p => vec![p],
+                    })
+                    .sorted()
+                    .collect_vec();
+                if sum.len() == 1 {
+                    return sum[0].clone();
+                }
+                Self::sum(sum)
+            }
+            Self::Par(p, q) => {
+                fn components(p: Process, acc: &mut Vec<Process>) {
+                    match p {
+                        Process::Par(p, q) => {
+                            components(*p, acc);
+                            components(*q, acc);
+                        }
+                        p => acc.push(p),
+                    }
+                }
+                let mut procs = vec![];
+                components(p.normalize(), &mut procs);
+                components(q.normalize(), &mut procs);
+                if procs.iter().all(|p| *p == Self::Skip) {
+                    return Self::Skip;
+                }
+                procs.retain(|p| *p != Self::Skip);
+                // a nil keeps the whole Par from terminating, so one stays unless no other
+                // component can terminate either
+                let has_nil = procs.iter().any(Self::is_nil);
+                procs.retain(|p| !p.is_nil());
+                if has_nil && procs.iter().any(Self::may_terminate) {
+                    procs.push(Self::nil());
+                }
+                procs.sort();
+                procs
+                    .into_iter()
+                    .rev()
+                    .reduce(|q, p| Self::par(p, q))
+                    .unwrap_or_else(Self::nil)
+            }
+            Self::Sync(p, mut chans, q) => {
+                let (p, q) = (p.normalize(), q.normalize());
+                if p.is_nil() && q.is_nil() {
+                    return Self::nil();
+                }
+                if p == Self::Skip && q == Self::Skip {
+                    return p;
+                }
+                chans.sort();
+                chans.dedup();
+                if p <= q {
+                    Self::sync(p, chans, q)
+                } else {
+                    Self::sync(q, chans, p)
+                }
+            }
+            Self::Substitution(p, subs) => Self::substitution(p.normalize(), subs),
+            Self::Restriction(p, chans, _) => {
+                let (p, mut chans) = match p.normalize() {
+                    Self::Restriction(p, inner, _) => (*p, [chans, inner].concat()),
+                    p => (p, chans),
+                };
+                if let Some(names) = p.channel_names() {
+                    chans.retain(|ch| names.contains(ch));
+                }
+                chans.sort();
+                chans.dedup();
+                if chans.is_empty() || p.is_nil() {
+                    return p;
+                }
+                Self::restriction(p, chans)
+            }
+            Self::Hiding(p, chans) => {
+                let (p, mut chans) = match p.normalize() {
+                    Self::Hiding(p, inner) => (*p, [chans, inner].concat()),
+                    p => (p, chans),
+                };
+                if let Some(names) = p.channel_names() {
+                    chans.retain(|ch| names.contains(ch));
+                }
+                chans.sort();
+                chans.dedup();
+                if chans.is_empty() || p.is_nil() {
+                    return p;
+                }
+                Self::hiding(p, chans)
+            }
+            Self::Buffered(p, chan, contents) => {
+                let mut buffers = vec![(chan, contents)];
+                let mut p = p.normalize();
+                while let Self::Buffered(inner, chan, contents) = p {
+                    buffers.push((chan, contents));
+                    p = *inner;
+                }
+                if p.is_nil() || p == Self::Skip {
+                    return p;
+                }
+                buffers
+                    .into_iter()
+                    .filter(|(_, contents)| !contents.is_empty())
+                    .sorted()
+                    .fold(p, |p, (chan, contents)| Self::buffered(p, chan, contents))
+            }
+            Self::Seq(p, q) => {
+                let p = p.normalize();
+                if p.is_nil() {
+                    return p;
+                }
+                if p == Self::Skip {
+                    return q.normalize();
+                }
+                Self::seq(p, q.normalize())
+            }
+            Self::Disrupt(p, q) => {
+                let (p, q) = (p.normalize(), q.normalize());
+                if p.is_nil() {
+                    return q;
+                }
+                if q.is_nil() || p == Self::Skip {
+                    return p;
+                }
+                Self::disrupt(p, q)
+            }
+            Self::Rec(x, p) => {
+                let p = p.normalize();
+                if !p.free_vars().contains(&x) {
+                    return p;
+                }
+                Self::rec(x, p)
+            }
+            Self::Replication(p, copies) => {
+                let p = p.normalize();
+                let copies = copies
+                    .into_iter()
+                    .map(Self::normalize)
+                    .filter(|c| !c.is_nil() && *c != Self::Skip && *c != p)
+                    .sorted()
+                    .collect_vec();
+                if p.is_nil() && copies.is_empty() {
+                    return p;
+                }
+                Self::replication(p, copies)
+            }
+        }
+    }
+    fn channel_names(&self) -> Option<HashSet<String>> {
+        match self {
+            Self::Constant(_, _) | Self::Rec(..) => None,
+            Self::Replication(p, copies) => {
+                [p.as_ref()]
+                    .into_iter()
+                    .chain(copies)
+                    .try_fold(HashSet::new(), |mut acc, p| {
+                        acc.extend(p.channel_names()?);
+                        Some(acc)
+                    })
+            }
+            Self::Skip => Some(HashSet::new()),
+            Self::Action(ch, p, _) | Self::Rated(ch, _, p, _) => {
+                let mut names = p.channel_names()?;
+                names.insert(ch.name().to_string());
+                Some(names)
+            }
+            Self::Delay(_, p) => p.channel_names(),
+            Self::Sum(sum) => sum.iter().try_fold(HashSet::new(), |mut acc, p| {
+                acc.extend(p.channel_names()?);
+                Some(acc)
+            }),
+            Self::Prob(branches) => branches.iter().try_fold(HashSet::new(), |mut acc, (_, p)| {
+                acc.extend(p.channel_names()?);
+                Some(acc)
+            }),
+            Self::Par(p, q) | Self::Sync(p, _, q) | Self::Seq(p, q) | Self::Disrupt(p, q) => {
+                let mut names = p.channel_names()?;
+                names.extend(q.channel_names()?);
+                Some(names)
+            }
+            Self::Substitution(p, subs) => Some(
+                p.channel_names()?
+                    .into_iter()
+                    .map(|name| subs.replace(name))
+                    .collect(),
+            ),
+            Self::Restriction(p, _, _) => p.channel_names(),
+            Self::Buffered(p, _, contents) => {
+                let mut names = p.channel_names()?;
+                names.extend(contents.iter().cloned());
+                Some(names)
+            }
+            Self::Hiding(p, chans) => Some(
+                p.channel_names()?
+                    .into_iter()
+                    .map(|name| {
+                        if chans.contains(&name) {
+                            Channel::tau().name().to_string()
+                        } else {
+                            name
+                        }
+                    })
+                    .collect(),
+            ),
         }
     }
     pub fn fold_consts(self, ctx: &Context) -> Self {
         let p = match self {
-            Self::Constant(name) => Self::Constant(name),
-            Self::Action(ch, p) => Self::action(ch, p.fold_consts(ctx)),
+            Self::Constant(_, _) | Self::Skip => self,
+            Self::Action(ch, p, _) => Self::action(ch, p.fold_consts(ctx)),
+            Self::Rated(ch, r, p, _) => Self::rated(ch, r, p.fold_consts(ctx)),
+            Self::Delay(n, p) => Self::delay(n, p.fold_consts(ctx)),
+            Self::Prob(b) => Self::Prob(
+                b.into_iter()
+                    .map(|(w, p)| (w, p.fold_consts(ctx)))
+                    .collect(),
+            ),
             Self::Sum(sum) => Self::sum(sum.into_iter().map(|p| p.fold_consts(ctx)).collect()),
             Self::Par(p, q) => Self::par(p.fold_consts(ctx), q.fold_consts(ctx)),
+            Self::Sync(p, a, q) => Self::sync(p.fold_consts(ctx), a, q.fold_consts(ctx)),
             Self::Substitution(p, s) => Self::substitution(p.fold_consts(ctx), s),
-            Self::Restriction(p, r) => Self::restriction(p.fold_consts(ctx), r),
+            Self::Restriction(p, r, _) => Self::restriction(p.fold_consts(ctx), r),
+            Self::Hiding(p, h) => Self::hiding(p.fold_consts(ctx), h),
+            Self::Buffered(p, c, b) => Self::buffered(p.fold_consts(ctx), c, b),
+            Self::Seq(p, q) => Self::seq(p.fold_consts(ctx), q.fold_consts(ctx)),
+            Self::Disrupt(p, q) => Self::disrupt(p.fold_consts(ctx), q.fold_consts(ctx)),
+            Self::Rec(x, p) => Self::rec(x, p.fold_consts(ctx)),
+            Self::Replication(p, c) => Self::replication(
+                p.fold_consts(ctx),
+                c.into_iter().map(|c| c.fold_consts(ctx)).collect(),
+            ),
         };
         if p.is_nil() {
             return p;
         }
         ctx.process_to_const(&p).unwrap_or(p)
     }
-    pub fn unfold_consts(self, ctx: &Context) -> Self {
-        fn unfold_rec(p: Process, ctx: &Context, seen: &mut HashSet<String>) -> Process {
-            match p {
-                Process::Constant(name) => {
+    pub fn unfold_consts(self, ctx: &Context) -> Result<Self> {
+        fn unfold_rec(p: Process, ctx: &Context, seen: &mut HashSet<String>) -> Result<Process> {
+            Ok(match p {
+                Process::Constant(name, loc) => {
                     if seen.contains(&name) {
-                        return Process::Constant(name);
+                        return Ok(Process::Constant(name, loc));
                     }
+                    let body = ctx
+                        .get_process(&name)
+                        .ok_or_else(|| Error::UndefinedConstant(name.clone()).at(loc.span()))?;
                     seen.insert(name.clone());
-                    let p = unfold_rec(ctx.get_process(&name).unwrap().clone(), ctx, seen);
+                    let p = unfold_rec(body.clone(), ctx, seen)?;
                     seen.remove(&name);
                     p
                 }
-                Process::Action(ch, p) => Process::action(ch, unfold_rec(*p, ctx, seen)),
-                Process::Sum(sum) => {
-                    Process::sum(sum.into_iter().map(|p| unfold_rec(p, ctx, seen)).collect())
-                }
+                Process::Action(ch, p, _) => Process::action(ch, unfold_rec(*p, ctx, seen)?),
+                Process::Rated(ch, r, p, _) => Process::rated(ch, r, unfold_rec(*p, ctx, seen)?),
+                Process::Delay(n, p) => Process::delay(n, unfold_rec(*p, ctx, seen)?),
+                Process::Prob(branches) => Process::Prob(
+                    branches
+                        .into_iter()
+                        .map(|(w, p)| Ok((w, unfold_rec(p, ctx, seen)?)))
+                        .collect::<Result<_>>()?,
+                ),
+                Process::Sum(sum) => Process::sum(
+                    sum.into_iter()
+                        .map(|p| unfold_rec(p, ctx, seen))
+                        .collect::<Result<_>>()?,
+                ),
                 Process::Par(p, q) => {
-                    Process::par(unfold_rec(*p, ctx, seen), unfold_rec(*q, ctx, seen))
+                    Process::par(unfold_rec(*p, ctx, seen)?, unfold_rec(*q, ctx, seen)?)
                 }
+                Process::Sync(p, chans, q) => Process::sync(
+                    unfold_rec(*p, ctx, seen)?,
+                    chans,
+                    unfold_rec(*q, ctx, seen)?,
+                ),
                 Process::Substitution(p, subs) => {
-                    Process::substitution(unfold_rec(*p, ctx, seen), subs)
+                    Process::substitution(unfold_rec(*p, ctx, seen)?, subs)
                 }
-                Process::Restriction(p, chans) => {
-                    Process::restriction(unfold_rec(*p, ctx, seen), chans)
+                Process::Restriction(p, chans, _) => {
+                    Process::restriction(unfold_rec(*p, ctx, seen)?, chans)
                 }
-            }
+                Process::Hiding(p, chans) => Process::hiding(unfold_rec(*p, ctx, seen)?, chans),
+                Process::Buffered(p, chan, contents) => {
+                    Process::buffered(unfold_rec(*p, ctx, seen)?, chan, contents)
+                }
+                Process::Seq(p, q) => {
+                    Process::seq(unfold_rec(*p, ctx, seen)?, unfold_rec(*q, ctx, seen)?)
+                }
+                Process::Disrupt(p, q) => {
+                    Process::disrupt(unfold_rec(*p, ctx, seen)?, unfold_rec(*q, ctx, seen)?)
+                }
+                Process::Rec(x, p) => {
+                    let inserted = seen.insert(x.clone());
+                    let p = unfold_rec(*p, ctx, seen)?;
+                    if inserted {
+                        seen.remove(&x);
+                    }
+                    Process::rec(x, p)
+                }
+                Process::Replication(p, copies) => Process::replication(
+                    unfold_rec(*p, ctx, seen)?,
+                    copies
+                        .into_iter()
+                        .map(|c| unfold_rec(c, ctx, seen))
+                        .collect::<Result<_>>()?,
+                ),
+                Process::Skip => p,
+            })
         }
         let mut seen = HashSet::new();
         if let Some(name) = ctx.name_of(&self) {
@@ -128,15 +777,86 @@ impl Process {
         unfold_rec(self, ctx, &mut seen)
     }
 
+    fn unfold_unguarded(self, ctx: &Context, seen: &mut HashSet<String>) -> Self {
+        match self {
+            Self::Constant(name, loc) => match ctx.get_process(&name) {
+                Some(p) if !seen.contains(&name) => {
+                    seen.insert(name.clone());
+                    let p = p.clone().unfold_unguarded(ctx, seen);
+                    seen.remove(&name);
+                    p
+                }
+                _ => Self::Constant(name, loc),
+            },
+            Self::Action(..) | Self::Rated(..) | Self::Delay(..) | Self::Skip => self,
+            Self::Prob(branches) => Self::Prob(
+                branches
+                    .into_iter()
+                    .map(|(w, p)| (w, p.unfold_unguarded(ctx, seen)))
+                    .collect(),
+            ),
+            Self::Sum(sum) => Self::sum(
+                sum.into_iter()
+                    .map(|p| p.unfold_unguarded(ctx, seen))
+                    .collect(),
+            ),
+            Self::Par(p, q) => {
+                Self::par(p.unfold_unguarded(ctx, seen), q.unfold_unguarded(ctx, seen))
+            }
+            Self::Sync(p, a, q) => Self::sync(
+                p.unfold_unguarded(ctx, seen),
+                a,
+                q.unfold_unguarded(ctx, seen),
+            ),
+            Self::Substitution(p, s) => Self::substitution(p.unfold_unguarded(ctx, seen), s),
+            Self::Restriction(p, r, _) => Self::restriction(p.unfold_unguarded(ctx, seen), r),
+            Self::Hiding(p, h) => Self::hiding(p.unfold_unguarded(ctx, seen), h),
+            Self::Buffered(p, c, b) => Self::buffered(p.unfold_unguarded(ctx, seen), c, b),
+            Self::Disrupt(p, q) => {
+                Self::disrupt(p.unfold_unguarded(ctx, seen), q.unfold_unguarded(ctx, seen))
+            }
+            Self::Replication(p, copies) => Self::replication(
+                p.unfold_unguarded(ctx, seen),
+                copies
+                    .into_iter()
+                    .map(|c| c.unfold_unguarded(ctx, seen))
+                    .collect(),
+            ),
+            Self::Rec(x, p) => {
+                let inserted = seen.insert(x.clone());
+                let p = p.unfold_unguarded(ctx, seen);
+                if inserted {
+                    seen.remove(&x);
+                }
+                Self::rec(x, p)
+            }
+            Self::Seq(p, q) => {
+                let p = p.unfold_unguarded(ctx, seen);
+                if p.is_done() {
+                    Self::seq(p, q.unfold_unguarded(ctx, seen))
+                } else {
+                    Self::seq(p, *q)
+                }
+            }
+        }
+    }
+
     pub fn derive_lts(self, ctx: &Context) -> Lts {
         let main = self.clone();
-        let unfolded = self.unfold_consts(ctx);
-        let mut transitions = unfolded.derive();
+        let unfold = |p: Process| p.unfold_unguarded(ctx, &mut HashSet::new()).normalize();
+        let unfolded = unfold(self);
+        let derive = |p: &Process| {
+            p.step(ctx)
+                .into_iter()
+                .map(|(_, a, q, _)| (p.clone(), a, unfold(q)))
+                .collect::<HashSet<_>>()
+        };
+        let mut transitions = derive(&unfolded);
         let mut len = 0;
         while transitions.len() != len {
             len = transitions.len();
             for t in transitions.clone() {
-                transitions.extend(t.2.derive());
+                transitions.extend(derive(&t.2));
             }
         }
         let transitions = transitions
@@ -148,68 +868,444 @@ impl Process {
             })
             .collect();
 
-        Lts::new(transitions).symbolic(ctx)
+        Lts::new(main, transitions).symbolic(ctx)
+    }
+    fn step(&self, ctx: &Context) -> Vec<RatedTransition> {
+        let p = self.clone().with_buffers(ctx, ctx.buffers().keys());
+        let mut ts = p.derive(ctx);
+        if ctx.is_timed() && ts.iter().all(|t| !t.1.is_tau()) {
+            ts.extend(p.tick().map(|q| (p.clone(), Channel::Tick, q, Weight(1.0))));
+        }
+        let max = ts.iter().map(|t| ctx.priority_of(&t.1)).max();
+        ts.into_iter()
+            .filter(|t| Some(ctx.priority_of(&t.1)) == max)
+            .collect()
+    }
+    pub fn distribution(self, ctx: &Context) -> Vec<(f64, Process)> {
+        let mut dist: Vec<(f64, Process)> = vec![];
+        for (v, p) in self.unfold_unguarded(ctx, &mut HashSet::new()).resolve() {
+            let p = p.normalize();
+            match dist.iter_mut().find(|(_, q)| *q == p) {
+                Some((w, _)) => *w += v,
+                None => dist.push((v, p)),
+            }
+        }
+        dist
+    }
+    fn resolve(self) -> Vec<(f64, Process)> {
+        fn product(
+            ps: Vec<(f64, Process)>,
+            qs: Vec<(f64, Process)>,
+            f: impl Fn(Process, Process) -> Process,
+        ) -> Vec<(f64, Process)> {
+            ps.into_iter()
+                .cartesian_product(qs)
+                .map(|((v, p), (w, q))| (v * w, f(p, q)))
+                .collect()
+        }
+        fn all(ps: Vec<Process>) -> Vec<(f64, Vec<Process>)> {
+            ps.into_iter().fold(vec![(1.0, vec![])], |acc, p| {
+                acc.into_iter()
+                    .cartesian_product(p.resolve())
+                    .map(|((v, mut ps), (w, p))| {
+                        ps.push(p);
+                        (v * w, ps)
+                    })
+                    .collect()
+            })
+        }
+        match self {
+            Process::Prob(branches) => {
+                let total = branches.iter().map(|(w, _)| w.0).sum::<f64>();
+                branches
+                    .into_iter()
+                    .flat_map(|(w, p)| {
+                        p.resolve()
+                            .into_iter()
+                            .map(move |(v, q)| (w.0 / total * v, q))
+                    })
+                    .collect()
+            }
+            Process::Sum(sum) => all(sum)
+                .into_iter()
+                .map(|(v, ps)| (v, Process::sum(ps)))
+                .collect(),
+            Process::Par(p, q) => product(p.resolve(), q.resolve(), Process::par),
+            Process::Sync(p, chans, q) => product(p.resolve(), q.resolve(), |p, q| {
+                Process::sync(p, chans.clone(), q)
+            }),
+            Process::Disrupt(p, q) => product(p.resolve(), q.resolve(), Process::disrupt),
+            Process::Seq(p, q) => p
+                .resolve()
+                .into_iter()
+                .map(|(v, p)| (v, Process::seq(p, *q.clone())))
+                .collect(),
+            Process::Substitution(p, subs) => p
+                .resolve()
+                .into_iter()
+                .map(|(v, p)| (v, Process::substitution(p, subs.clone())))
+                .collect(),
+            Process::Restriction(p, chans, _) => p
+                .resolve()
+                .into_iter()
+                .map(|(v, p)| (v, Process::restriction(p, chans.clone())))
+                .collect(),
+            Process::Hiding(p, chans) => p
+                .resolve()
+                .into_iter()
+                .map(|(v, p)| (v, Process::hiding(p, chans.clone())))
+                .collect(),
+            Process::Buffered(p, chan, contents) => p
+                .resolve()
+                .into_iter()
+                .map(|(v, p)| (v, Process::buffered(p, chan.clone(), contents.clone())))
+                .collect(),
+            Process::Rec(ref x, ref p) => p
+                .as_ref()
+                .clone()
+                .resolve()
+                .into_iter()
+                .map(|(v, p)| (v, p.replace_var(x, &self)))
+                .collect(),
+            Process::Replication(p, copies) => all(copies)
+                .into_iter()
+                .map(|(v, copies)| (v, Process::replication(*p.clone(), copies)))
+                .collect(),
+            _ => vec![(1.0, self)],
+        }
     }
-    fn derive(&self) -> HashSet<Transition> {
+    fn tick(&self) -> Option<Process> {
         match self {
-            Process::Constant(_) => Default::default(),
-            Process::Action(a, p) => [(self.clone(), a.clone(), *p.clone())]
+            Process::Constant(_, _)
+            | Process::Action(Channel::Tau, _, _)
+            | Process::Rated(Channel::Tau, ..) => None,
+            Process::Action(..) | Process::Rated(..) | Process::Skip => Some(self.clone()),
+            Process::Delay(n, p) if *n <= 1 => Some(*p.clone()),
+            Process::Delay(n, p) => Some(Process::delay(n - 1, *p.clone())),
+            Process::Sum(sum) => Some(Process::sum(
+                sum.iter().map(Process::tick).collect::<Option<_>>()?,
+            )),
+            Process::Prob(branches) => Some(Process::Prob(
+                branches
+                    .iter()
+                    .map(|(w, p)| Some((*w, p.tick()?)))
+                    .collect::<Option<_>>()?,
+            )),
+            Process::Par(p, q) => Some(Process::par(p.tick()?, q.tick()?)),
+            Process::Sync(p, chans, q) => Some(Process::sync(p.tick()?, chans.clone(), q.tick()?)),
+            Process::Seq(p, q) => Some(Process::seq(p.tick()?, *q.clone())),
+            Process::Disrupt(p, q) => Some(Process::disrupt(p.tick()?, q.tick()?)),
+            Process::Substitution(p, subs) => Some(Process::substitution(p.tick()?, subs.clone())),
+            Process::Restriction(p, chans, _) => {
+                Some(Process::restriction(p.tick()?, chans.clone()))
+            }
+            Process::Hiding(p, chans) => Some(Process::hiding(p.tick()?, chans.clone())),
+            Process::Buffered(p, chan, contents) => {
+                Some(Process::buffered(p.tick()?, chan.clone(), contents.clone()))
+            }
+            Process::Rec(x, p) => Some(p.tick()?.replace_var(x, self)),
+            Process::Replication(p, copies) => Some(Process::replication(
+                *p.clone(),
+                copies.iter().map(Process::tick).collect::<Option<_>>()?,
+            )),
+        }
+    }
+    // identical moves are kept apart so that their rates add up in a ctmc
+    fn derive(&self, ctx: &Context) -> Vec<RatedTransition> {
+        match self {
+            Process::Delay(_, _) if ctx.is_timed() => Default::default(),
+            Process::Delay(_, p) => p
+                .derive(ctx)
+                .into_iter()
+                .map(|t| (self.clone(), t.1, t.2, t.3))
+                .collect(),
+            Process::Constant(_, _) | Process::Skip => Default::default(),
+            Process::Action(a, p, _) => [(self.clone(), a.clone(), *p.clone(), Weight(1.0))]
+                .into_iter()
+                .collect(),
+            Process::Rated(a, r, p, _) => [(self.clone(), a.clone(), *p.clone(), *r)]
+                .into_iter()
+                .collect(),
+
+            Process::Restriction(p, chans, _) => p
+                .as_ref()
+                .clone()
+                .with_buffers(ctx, chans)
+                .derive(ctx)
                 .into_iter()
+                .filter_map(|t| match t.1 {
+                    Channel::Send(ref name) if chans.contains(name) && ctx.is_broadcast(name) => {
+                        Some((self.clone(), Channel::tau(), t.2, t.3))
+                    }
+                    _ if chans.contains(&t.1.name().to_string())
+                        && ctx.buffer_of(t.1.name()).is_some() =>
+                    {
+                        Some((self.clone(), Channel::tau(), t.2, t.3))
+                    }
+                    _ if chans.contains(&t.1.name().to_string()) => None,
+                    _ => Some((self.clone(), t.1, t.2, t.3)),
+                })
+                .map(|t| (t.0, t.1, Process::restriction(t.2, chans.clone()), t.3))
                 .collect(),
 
-            Process::Restriction(p, chans) => p
-                .derive()
+            Process::Hiding(p, chans) => p
+                .derive(ctx)
                 .into_iter()
-                .filter(|t| !chans.contains(&t.1.name().to_string()))
-                .map(|t| (self.clone(), t.1, Process::restriction(t.2, chans.clone())))
+                .map(|t| {
+                    let ch = if chans.contains(&t.1.name().to_string()) {
+                        Channel::tau()
+                    } else {
+                        t.1
+                    };
+                    (self.clone(), ch, Process::hiding(t.2, chans.clone()), t.3)
+                })
+                .collect(),
+
+            Process::Buffered(p, chan, contents) => p
+                .derive(ctx)
+                .into_iter()
+                .filter_map(|t| {
+                    if ctx.buffer_of(t.1.name()) != Some(chan) {
+                        let p = Process::buffered(t.2, chan.clone(), contents.clone());
+                        return Some((t.1, p, t.3));
+                    }
+                    let contents = match &t.1 {
+                        Channel::Send(name) if ctx.buffer_has_room(chan, contents.len()) => {
+                            [contents.clone(), vec![name.clone()]].concat()
+                        }
+                        Channel::Recv(name) if contents.first() == Some(name) => {
+                            contents[1..].to_vec()
+                        }
+                        _ => return None,
+                    };
+                    Some((t.1, Process::buffered(t.2, chan.clone(), contents), t.3))
+                })
+                .map(|(ch, p, r)| (self.clone(), ch, p, r))
                 .collect(),
 
             Process::Substitution(p, subs) => p
-                .derive()
+                .derive(ctx)
                 .into_iter()
                 .map(|t| {
                     (
                         self.clone(),
                         subs.replace_channel(t.1),
                         Process::substitution(t.2, subs.clone()),
+                        t.3,
                     )
                 })
                 .collect(),
 
             Process::Sum(sum) => sum
                 .iter()
-                .flat_map(|p| p.derive())
-                .map(|t| (self.clone(), t.1, t.2))
+                .flat_map(|p| p.derive(ctx))
+                .map(|t| (self.clone(), t.1, t.2, t.3))
+                .collect(),
+
+            Process::Prob(branches) => branches
+                .iter()
+                .flat_map(|(_, p)| p.derive(ctx))
+                .map(|t| (self.clone(), t.1, t.2, t.3))
+                .collect(),
+
+            Process::Seq(p, q) => {
+                let mut ts = p
+                    .derive(ctx)
+                    .into_iter()
+                    .map(|t| (self.clone(), t.1, Process::seq(t.2, *q.clone()), t.3))
+                    .collect_vec();
+                if p.is_done() {
+                    ts.extend(
+                        q.derive(ctx)
+                            .into_iter()
+                            .map(|t| (self.clone(), t.1, t.2, t.3)),
+                    );
+                }
+                ts
+            }
+
+            Process::Replication(p, copies) => {
+                let mut ts = vec![];
+                let room = (ctx.replication_bound() as usize).saturating_sub(copies.len());
+                let ptrans = p.derive(ctx);
+                let ctrans = copies.iter().map(|c| c.derive(ctx)).collect_vec();
+                let is_synched = |a: &Channel, b: &Channel| {
+                    !a.is_tau()
+                        && !ctx.is_broadcast(a.name())
+                        && ctx.buffer_of(a.name()).is_none()
+                        && a.is_synched_with(b)
+                };
+                let next = |replaced: &[(usize, &Process)], spawned: &[&Process]| {
+                    let mut copies = copies.clone();
+                    for (i, c) in replaced {
+                        copies[*i] = (*c).clone();
+                    }
+                    copies.extend(spawned.iter().map(|c| (*c).clone()));
+                    Process::replication(*p.clone(), copies)
+                };
+
+                for (i, cts) in ctrans.iter().enumerate() {
+                    for ct in cts {
+                        ts.push((self.clone(), ct.1.clone(), next(&[(i, &ct.2)], &[]), ct.3));
+                    }
+                }
+                if room >= 1 {
+                    for pt in &ptrans {
+                        ts.push((self.clone(), pt.1.clone(), next(&[], &[&pt.2]), pt.3));
+                    }
+                }
+                for (i, j) in (0..copies.len()).tuple_combinations() {
+                    for ct in &ctrans[i] {
+                        for dt in ctrans[j].iter().filter(|dt| is_synched(&ct.1, &dt.1)) {
+                            let q = next(&[(i, &ct.2), (j, &dt.2)], &[]);
+                            ts.push((self.clone(), Channel::tau(), q, ct.3 * dt.3));
+                        }
+                    }
+                }
+                if room >= 1 {
+                    for (i, cts) in ctrans.iter().enumerate() {
+                        for ct in cts {
+                            for pt in ptrans.iter().filter(|pt| is_synched(&ct.1, &pt.1)) {
+                                let q = next(&[(i, &ct.2)], &[&pt.2]);
+                                ts.push((self.clone(), Channel::tau(), q, ct.3 * pt.3));
+                            }
+                        }
+                    }
+                }
+                if room >= 2 {
+                    for (pt, qt) in ptrans.iter().tuple_combinations() {
+                        if is_synched(&pt.1, &qt.1) {
+                            let q = next(&[], &[&pt.2, &qt.2]);
+                            ts.push((self.clone(), Channel::tau(), q, pt.3 * qt.3));
+                        }
+                    }
+                }
+                ts
+            }
+
+            Process::Rec(x, p) => p
+                .derive(ctx)
+                .into_iter()
+                .map(|t| (self.clone(), t.1, t.2.replace_var(x, self), t.3))
+                .collect(),
+
+            Process::Disrupt(p, q) => p
+                .derive(ctx)
+                .into_iter()
+                .map(|t| (self.clone(), t.1, Process::disrupt(t.2, *q.clone()), t.3))
+                .chain(
+                    q.derive(ctx)
+                        .into_iter()
+                        .map(|t| (self.clone(), t.1, t.2, t.3)),
+                )
                 .collect(),
 
             Process::Par(p, q) => {
-                let mut ts = HashSet::new();
-                let mut ptrans = p.derive();
-                let mut qtrans = q.derive();
-
-                for pt in ptrans.clone() {
-                    for qt in qtrans.clone() {
-                        if pt.1.is_synched_with(&qt.1) {
-                            ts.insert((
+                let mut ts = vec![];
+                let ptrans = p.derive(ctx);
+                let qtrans = q.derive(ctx);
+                let is_broadcast = |ch: &Channel| !ch.is_tau() && ctx.is_broadcast(ch.name());
+                let receivers = |ts: &[RatedTransition], ch: &Channel| {
+                    ts.iter()
+                        .filter(|t| t.1 == Channel::recv(ch.name()))
+                        .map(|t| (t.2.clone(), t.3))
+                        .collect_vec()
+                };
+
+                // transitions that synchronise are not interleaved
+                let mut psynched = vec![false; ptrans.len()];
+                let mut qsynched = vec![false; qtrans.len()];
+                for (i, pt) in ptrans.iter().enumerate() {
+                    for (j, qt) in qtrans.iter().enumerate() {
+                        if !is_broadcast(&pt.1)
+                            && ctx.buffer_of(pt.1.name()).is_none()
+                            && pt.1.is_synched_with(&qt.1)
+                        {
+                            ts.push((
                                 self.clone(),
                                 Channel::tau(),
                                 Process::par(pt.2.clone(), qt.2.clone()),
+                                pt.3 * qt.3,
                             ));
-                            ptrans.remove(&pt);
-                            qtrans.remove(&qt);
+                            psynched[i] = true;
+                            qsynched[j] = true;
                         }
                     }
                 }
-                let ptrans = ptrans
-                    .into_iter()
-                    .map(|t| (self.clone(), t.1, Process::par(t.2, *q.clone())));
+                for (pt, _) in ptrans.iter().zip(&psynched).filter(|(_, s)| !**s) {
+                    let qs = if is_broadcast(&pt.1) {
+                        receivers(&qtrans, &pt.1)
+                    } else {
+                        vec![]
+                    };
+                    if qs.is_empty() {
+                        ts.push((
+                            self.clone(),
+                            pt.1.clone(),
+                            Process::par(pt.2.clone(), *q.clone()),
+                            pt.3,
+                        ));
+                    }
+                    for (q2, r) in qs {
+                        let p2 = Process::par(pt.2.clone(), q2);
+                        ts.push((self.clone(), pt.1.clone(), p2, pt.3 * r));
+                    }
+                }
+                for (qt, _) in qtrans.iter().zip(&qsynched).filter(|(_, s)| !**s) {
+                    let ps = if is_broadcast(&qt.1) {
+                        receivers(&ptrans, &qt.1)
+                    } else {
+                        vec![]
+                    };
+                    if ps.is_empty() {
+                        ts.push((
+                            self.clone(),
+                            qt.1.clone(),
+                            Process::par(*p.clone(), qt.2.clone()),
+                            qt.3,
+                        ));
+                    }
+                    for (p2, r) in ps {
+                        let q2 = Process::par(p2, qt.2.clone());
+                        ts.push((self.clone(), qt.1.clone(), q2, qt.3 * r));
+                    }
+                }
+                ts
+            }
 
-                let qtrans = qtrans
-                    .into_iter()
-                    .map(|t| (self.clone(), t.1, Process::par(*p.clone(), t.2)));
+            Process::Sync(p, chans, q) => {
+                let mut ts = vec![];
+                let ptrans = p.derive(ctx);
+                let qtrans = q.derive(ctx);
+                let is_synched =
+                    |ch: &Channel| !ch.is_tau() && chans.iter().any(|c| c == ch.name());
 
-                ts.extend(ptrans);
-                ts.extend(qtrans);
+                for pt in ptrans.iter().filter(|t| is_synched(&t.1)) {
+                    for qt in qtrans.iter().filter(|t| t.1 == pt.1) {
+                        ts.push((
+                            self.clone(),
+                            pt.1.clone(),
+                            Process::sync(pt.2.clone(), chans.clone(), qt.2.clone()),
+                            pt.3 * qt.3,
+                        ));
+                    }
+                }
+                for pt in ptrans.into_iter().filter(|t| !is_synched(&t.1)) {
+                    ts.push((
+                        self.clone(),
+                        pt.1,
+                        Process::sync(pt.2, chans.clone(), *q.clone()),
+                        pt.3,
+                    ));
+                }
+                for qt in qtrans.into_iter().filter(|t| !is_synched(&t.1)) {
+                    ts.push((
+                        self.clone(),
+                        qt.1,
+                        Process::sync(*p.clone(), chans.clone(), qt.2),
+                        qt.3,
+                    ));
+                }
                 ts
             }
         }
@@ -218,8 +1314,19 @@ impl Process {
 impl Display for Process {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Process::Constant(k) => write!(f, "{}", k),
-            Process::Action(ch, p) => write!(f, "{}.{}", ch, p),
+            Process::Constant(k, _) => write!(f, "{}", k),
+            Process::Action(ch, p, _) => write!(f, "{}.{}", ch, p),
+            Process::Rated(ch, r, p, _) => write!(f, "({}, {}).{}", ch, r, p),
+            Process::Delay(n, p) => write!(f, "delay({}).{}", n, p),
+            Process::Prob(branches) if branches.is_empty() => write!(f, "NIL"),
+            Process::Prob(branches) => {
+                let (w, p) = &branches[0];
+                write!(f, "({} [{}]", p, w)?;
+                for (w, p) in &branches[1..] {
+                    write!(f, " + [{}] {}", w, p)?;
+                }
+                write!(f, ")")
+            }
             Process::Sum(procs) => {
                 if procs.is_empty() {
                     write!(f, "NIL")
@@ -228,9 +1335,29 @@ impl Display for Process {
                 }
             }
             Process::Par(p, q) => write!(f, "({} | {})", p, q),
-            Process::Restriction(p, chans) => {
+            Process::Seq(p, q) => write!(f, "({} ; {})", p, q),
+            Process::Skip => write!(f, "SKIP"),
+            Process::Disrupt(p, q) => write!(f, "({} [> {})", p, q),
+            Process::Rec(x, p) => write!(f, "rec {}. {}", x, p),
+            Process::Replication(p, copies) => {
+                if copies.is_empty() {
+                    write!(f, "!{}", p)
+                } else {
+                    write!(f, "({} | !{})", copies.iter().join(" | "), p)
+                }
+            }
+            Process::Sync(p, chans, q) => {
+                write!(f, "({} [| {{ {} }} |] {})", p, chans.iter().join(", "), q)
+            }
+            Process::Restriction(p, chans, _) => {
                 write!(f, "({} \\ {{ {} }})", p, chans.iter().join(", "))
             }
+            Process::Hiding(p, chans) => {
+                write!(f, "({} / {{ {} }})", p, chans.iter().join(", "))
+            }
+            Process::Buffered(p, chan, contents) => {
+                write!(f, "({} @ {}[{}])", p, chan, contents.iter().join(", "))
+            }
             Process::Substitution(p, subs) => write!(
                 f,
                 "({p}[{}])",
@@ -243,16 +1370,94 @@ impl Display for Process {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct Substitution(Vec<(String, String)>);
+#[derive(Clone, Copy, Debug)]
+pub struct Weight(pub f64);
+impl PartialEq for Weight {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+impl Eq for Weight {}
+impl Hash for Weight {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+impl PartialOrd for Weight {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Weight {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+impl Mul for Weight {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Weight(self.0 * other.0)
+    }
+}
+impl Display for Weight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Explorer<'a> {
+    ctx: &'a Context,
+    init: Process,
+}
+impl<'a> Explorer<'a> {
+    pub fn new(ctx: &'a Context, init: Process) -> Self {
+        Self {
+            ctx,
+            init: init.normalize(),
+        }
+    }
+    pub fn rates(&self, s: &Process) -> Vec<(Channel, f64, Process)> {
+        s.clone()
+            .unfold_unguarded(self.ctx, &mut HashSet::new())
+            .step(self.ctx)
+            .into_iter()
+            .map(|t| (t.1, t.3 .0, t.2.normalize()))
+            .collect()
+    }
+}
+impl TransitionSystem for Explorer<'_> {
+    type State = Process;
+    type Label = Channel;
+
+    fn initial(&self) -> Process {
+        self.init.clone()
+    }
+    fn successors(&self, s: &Process) -> Vec<(Channel, Process)> {
+        self.rates(s)
+            .into_iter()
+            .map(|(a, _, q)| (a, q))
+            .unique()
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Substitution(Vec<(String, String)>, Loc);
 impl Substitution {
     pub fn new(subs: Vec<(String, String)>) -> Self {
-        assert!(subs.iter().all(|(new, old)| new != "tau" && old != "tau"));
-        Self(subs)
+        Self(subs, Loc::default())
+    }
+    pub fn with_loc(self, loc: Loc) -> Self {
+        Self(self.0, loc)
     }
     pub fn pairs(&self) -> &[(String, String)] {
         &self.0
     }
+    pub fn span(&self) -> Option<Span> {
+        self.1.span()
+    }
     pub fn replace(&self, name: String) -> String {
         self.0
             .iter()
@@ -264,14 +1469,41 @@ impl Substitution {
             Channel::Send(name) => Channel::Send(self.replace(name)),
             Channel::Recv(name) => Channel::Recv(self.replace(name)),
             Channel::Tau => Channel::Tau,
+            Channel::Tick => Channel::Tick,
         }
     }
     pub fn replace_process(&self, p: Process) -> Process {
         match p {
-            Process::Constant(_) => p,
-            Process::Action(ch, p) => {
+            Process::Constant(_, _) | Process::Skip => p,
+            Process::Delay(n, p) => Process::Delay(n, Box::new(self.replace_process(*p))),
+            Process::Prob(branches) => Process::Prob(
+                branches
+                    .into_iter()
+                    .map(|(w, p)| (w, self.replace_process(p)))
+                    .collect(),
+            ),
+            Process::Seq(p, q) => Process::Seq(
+                Box::new(self.replace_process(*p)),
+                Box::new(self.replace_process(*q)),
+            ),
+            Process::Disrupt(p, q) => Process::Disrupt(
+                Box::new(self.replace_process(*p)),
+                Box::new(self.replace_process(*q)),
+            ),
+            Process::Rec(x, p) => Process::Rec(x, Box::new(self.replace_process(*p))),
+            Process::Replication(p, copies) => Process::Replication(
+                Box::new(self.replace_process(*p)),
+                copies
+                    .into_iter()
+                    .map(|c| self.replace_process(c))
+                    .collect(),
+            ),
+            Process::Action(ch, p, _) => {
                 Process::action(self.replace_channel(ch), self.replace_process(*p))
             }
+            Process::Rated(ch, r, p, _) => {
+                Process::rated(self.replace_channel(ch), r, self.replace_process(*p))
+            }
             Process::Sum(sum) => {
                 Process::Sum(sum.into_iter().map(|p| self.replace_process(p)).collect())
             }
@@ -279,11 +1511,20 @@ impl Substitution {
                 Box::new(self.replace_process(*p)),
                 Box::new(self.replace_process(*q)),
             ),
+            Process::Sync(p, chans, q) => Process::Sync(
+                Box::new(self.replace_process(*p)),
+                chans,
+                Box::new(self.replace_process(*q)),
+            ),
             Process::Substitution(p, f) => {
                 Process::Substitution(Box::new(self.replace_process(*p)), f)
             }
-            Process::Restriction(p, chans) => {
-                Process::Restriction(Box::new(self.replace_process(*p)), chans)
+            Process::Restriction(p, chans, loc) => {
+                Process::Restriction(Box::new(self.replace_process(*p)), chans, loc)
+            }
+            Process::Hiding(p, chans) => Process::Hiding(Box::new(self.replace_process(*p)), chans),
+            Process::Buffered(p, chan, contents) => {
+                Process::Buffered(Box::new(self.replace_process(*p)), chan, contents)
             }
         }
     }
@@ -296,17 +1537,20 @@ impl Substitution {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Channel {
     Send(String),
     Recv(String),
     Tau,
+    Tick,
 }
 impl Channel {
     pub fn name(&self) -> &str {
         match self {
             Self::Send(s) | Self::Recv(s) => s,
             Self::Tau => "tau",
+            // not a valid identifier, so it never clashes with a user channel
+            Self::Tick => "√",
         }
     }
     pub fn send(s: &str) -> Self {
@@ -321,6 +1565,9 @@ impl Channel {
     pub fn is_tau(&self) -> bool {
         self == &Self::Tau
     }
+    pub fn is_tick(&self) -> bool {
+        self == &Self::Tick
+    }
     pub fn is_synched_with(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Send(a), Self::Recv(b)) | (Self::Recv(a), Self::Send(b)) => *a == *b,
@@ -336,6 +1583,69 @@ impl Display for Channel {
             Channel::Send(s) => write!(f, "{}!", s),
             Channel::Recv(s) => write!(f, "{}?", s),
             Channel::Tau => write!(f, "Tau"),
+            Channel::Tick => write!(f, "Tick"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lts(source: &str) -> (Context, Lts) {
+        let ctx = Context::try_from(source).unwrap();
+        let lts = ctx.to_lts().unwrap();
+        (ctx, lts)
+    }
+
+    #[test]
+    fn par_and_sum_ignore_order_and_nesting() {
+        let [a, b, c] = ["a", "b", "c"].map(|x| Process::action(Channel::send(x), Process::nil()));
+        let left = Process::par(Process::par(a.clone(), b.clone()), c.clone());
+        let right = Process::par(c.clone(), Process::par(b.clone(), a.clone()));
+        assert_eq!(left.normalize(), right.normalize());
+        let left = Process::sum(vec![Process::sum(vec![a.clone(), b.clone()]), c.clone()]);
+        let right = Process::sum(vec![c, Process::sum(vec![b, a])]);
+        assert_eq!(left.normalize(), right.normalize());
+    }
+
+    #[test]
+    fn unused_restrictions_are_dropped() {
+        let a = Process::action(Channel::send("a"), Process::nil());
+        let p = Process::restriction(a.clone(), vec!["b".to_string()]);
+        assert_eq!(p.normalize(), a);
+    }
+
+    #[test]
+    fn congruent_states_are_merged() {
+        let (_, lts) = lts("#![start(Main)]\nfn Main() { ((a!.nil | b!.nil) | c!.nil) }");
+        assert_eq!(lts.nodes().len(), 8);
+        assert_eq!(lts.transitions().len(), 12);
+        let done = lts.nodes().into_iter().filter(|p| p.is_nil()).count();
+        assert_eq!(done, 1);
+    }
+
+    #[test]
+    fn nil_beside_skip_is_stuck() {
+        let (ctx, lts) = lts("#![start(Main)]\nfn Main() { ((nil | skip) ; b!.nil) }");
+        assert!(lts.transitions().is_empty());
+        assert!(lts.deadlocks(&ctx).contains(lts.init()));
+    }
+
+    #[test]
+    fn nil_stays_beside_a_process_that_may_terminate() {
+        let a = Process::action(Channel::send("a"), Process::skip());
+        assert_ne!(Process::par(Process::nil(), a.clone()).normalize(), a);
+        assert_ne!(
+            Process::par(Process::skip(), Process::nil()).normalize(),
+            Process::skip()
+        );
+    }
+
+    #[test]
+    fn skip_and_nil_go_when_they_cannot_change_termination() {
+        let a = Process::action(Channel::send("a"), Process::nil());
+        assert_eq!(Process::par(Process::skip(), a.clone()).normalize(), a);
+        assert_eq!(Process::par(a.clone(), Process::nil()).normalize(), a);
+    }
+}
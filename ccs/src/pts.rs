@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::context::Context;
+use crate::process::{Channel, Explorer, Process};
+use crate::transition_system::TransitionSystem;
+
+const EPSILON: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 100_000;
+
+pub type Distribution = Vec<(usize, f64)>;
+
+#[derive(Clone, Debug)]
+pub struct Pts {
+    states: Vec<Process>,
+    choices: Vec<Vec<(Channel, Distribution)>>,
+}
+impl Pts {
+    pub fn new(ctx: &Context, init: Process) -> Self {
+        let explorer = Explorer::new(ctx, init);
+        let mut pts = Self {
+            states: vec![],
+            choices: vec![],
+        };
+        let mut index = HashMap::new();
+        let mut id_of = |p: Process, pts: &mut Self| {
+            *index.entry(p.clone()).or_insert_with(|| {
+                pts.states.push(p);
+                pts.choices.push(vec![]);
+                pts.states.len() - 1
+            })
+        };
+
+        let init = explorer.initial();
+        let dist = init.clone().distribution(ctx);
+        if dist.len() == 1 {
+            id_of(dist[0].1.clone(), &mut pts);
+        } else {
+            let s = id_of(init, &mut pts);
+            let dist = dist
+                .into_iter()
+                .map(|(v, p)| (id_of(p, &mut pts), v))
+                .collect();
+            pts.choices[s].push((Channel::tau(), dist));
+        }
+        let mut next = 0;
+        while next < pts.states.len() {
+            let s = next;
+            next += 1;
+            if !pts.choices[s].is_empty() {
+                continue;
+            }
+            for (a, q) in explorer.successors(&pts.states[s].clone()) {
+                let dist = q
+                    .distribution(ctx)
+                    .into_iter()
+                    .map(|(v, p)| (id_of(p, &mut pts), v))
+                    .collect();
+                pts.choices[s].push((a, dist));
+            }
+        }
+        pts
+    }
+    pub fn init(&self) -> usize {
+        0
+    }
+    pub fn states(&self) -> &[Process] {
+        &self.states
+    }
+    pub fn choices(&self, s: usize) -> &[(Channel, Distribution)] {
+        &self.choices[s]
+    }
+    pub fn is_dtmc(&self) -> bool {
+        self.choices.iter().all(|c| c.len() <= 1)
+    }
+    pub fn deadlocks(&self) -> HashSet<usize> {
+        (0..self.states.len())
+            .filter(|s| self.choices[*s].is_empty() && !self.states[*s].is_done())
+            .collect()
+    }
+    pub fn enabling(&self, ch: &Channel) -> HashSet<usize> {
+        (0..self.states.len())
+            .filter(|s| self.choices[*s].iter().any(|(a, _)| a == ch))
+            .collect()
+    }
+    pub fn reachability(&self, targets: &HashSet<usize>, max: bool) -> Vec<f64> {
+        let mut x = (0..self.states.len())
+            .map(|s| if targets.contains(&s) { 1.0 } else { 0.0 })
+            .collect_vec();
+        for _ in 0..MAX_ITERATIONS {
+            let next = (0..self.states.len())
+                .map(|s| {
+                    if targets.contains(&s) {
+                        return 1.0;
+                    }
+                    let values = self.choices[s]
+                        .iter()
+                        .map(|(_, dist)| dist.iter().map(|(t, v)| v * x[*t]).sum::<f64>());
+                    if max {
+                        values.fold(0.0, f64::max)
+                    } else {
+                        values.reduce(f64::min).unwrap_or(0.0)
+                    }
+                })
+                .collect_vec();
+            let delta = next
+                .iter()
+                .zip(&x)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            x = next;
+            if delta < EPSILON {
+                break;
+            }
+        }
+        x
+    }
+
+    pub fn to_prism_tra(&self) -> String {
+        let choices = self.choices.iter().map(Vec::len).sum::<usize>();
+        let transitions = self
+            .choices
+            .iter()
+            .flatten()
+            .map(|c| c.1.len())
+            .sum::<usize>();
+        let mut lines = vec![format!("{} {} {}", self.states.len(), choices, transitions)];
+        for (s, choices) in self.choices.iter().enumerate() {
+            for (c, (a, dist)) in choices.iter().enumerate() {
+                for (t, v) in dist {
+                    lines.push(format!("{s} {c} {t} {v} {}", prism_action(a)));
+                }
+            }
+        }
+        lines
+            .into_iter()
+            .map(|l| l.trim_end().to_string() + "\n")
+            .collect()
+    }
+    pub fn to_prism_sta(&self) -> String {
+        let states = (0..self.states.len()).map(|s| format!("{s}:({s})\n"));
+        ["(s)\n".to_string()].into_iter().chain(states).collect()
+    }
+    pub fn to_prism_lab(&self) -> String {
+        let deadlocks = self.deadlocks();
+        let labels = (0..self.states.len()).filter_map(|s| {
+            let ids = [(s == self.init(), 0), (deadlocks.contains(&s), 1)]
+                .into_iter()
+                .filter(|(has, _)| *has)
+                .map(|(_, id)| id)
+                .join(" ");
+            (!ids.is_empty()).then(|| format!("{s}: {ids}\n"))
+        });
+        ["0=\"init\" 1=\"deadlock\"\n".to_string()]
+            .into_iter()
+            .chain(labels)
+            .collect()
+    }
+}
+
+fn prism_action(ch: &Channel) -> String {
+    match ch {
+        Channel::Send(name) => format!("{name}_send"),
+        Channel::Recv(name) => format!("{name}_recv"),
+        Channel::Tau => String::new(),
+        Channel::Tick => "tick".to_string(),
+    }
+}
@@ -0,0 +1,551 @@
+use itertools::Itertools;
+
+use crate::context::Context;
+use crate::process::{Channel, Process, Substitution};
+
+const MAX_STEPS: usize = 1000;
+
+type Law = (&'static str, fn(&Process, &Context) -> Option<Process>);
+
+const SIMPLIFY: &[Law] = &[
+    ("sum-nil", sum_nil),
+    ("sum-assoc", sum_assoc),
+    ("sum-idem", sum_idem),
+    ("par-nil", par_nil),
+    ("par-skip", par_skip),
+    ("res-nil", res_nil),
+    ("res-res", res_res),
+    ("rel-nil", rel_nil),
+    ("rel-rel", rel_rel),
+    ("hide-nil", hide_nil),
+    ("seq-skip", seq_skip),
+    ("seq-nil", seq_nil),
+    ("dis-nil", dis_nil),
+    ("rep-nil", rep_nil),
+];
+const EXPAND: &[Law] = &[
+    ("res-prefix", res_prefix),
+    ("res-sum", res_sum),
+    ("rel-prefix", rel_prefix),
+    ("rel-sum", rel_sum),
+    ("hide-prefix", hide_prefix),
+    ("hide-sum", hide_sum),
+    ("seq-prefix", seq_prefix),
+    ("seq-sum", seq_sum),
+    ("dis-expand", dis_expand),
+    ("expansion", expansion),
+];
+const NORMALIZE: &[Law] = &[("sum-comm", sum_comm)];
+const UNFOLD: &[Law] = &[("unfold", unfold)];
+
+#[derive(Clone, Debug)]
+pub struct Derivation {
+    start: Process,
+    steps: Vec<(&'static str, Process)>,
+}
+impl Derivation {
+    pub fn start(&self) -> &Process {
+        &self.start
+    }
+    pub fn steps(&self) -> &[(&'static str, Process)] {
+        &self.steps
+    }
+    pub fn result(&self) -> &Process {
+        self.steps.last().map(|(_, p)| p).unwrap_or(&self.start)
+    }
+    fn terms(&self) -> impl Iterator<Item = &Process> {
+        [&self.start]
+            .into_iter()
+            .chain(self.steps.iter().map(|(_, p)| p))
+    }
+}
+
+pub fn simplify(p: Process) -> Derivation {
+    rewrite(p, &[SIMPLIFY], &Context::new(), true)
+}
+pub fn expand(p: Process, ctx: &Context) -> Derivation {
+    rewrite(p, &[&[SIMPLIFY, EXPAND].concat(), UNFOLD], ctx, false)
+}
+pub fn prove(p: Process, q: Process, ctx: &Context) -> Option<Derivation> {
+    let laws = [&[SIMPLIFY, EXPAND, NORMALIZE].concat(), UNFOLD];
+    let lhs = rewrite(p, &laws, ctx, true);
+    let rhs = rewrite(q, &laws, ctx, true);
+    let (i, j) = lhs
+        .terms()
+        .enumerate()
+        .find_map(|(i, p)| rhs.terms().position(|q| p == q).map(|j| (i, j)))?;
+
+    let mut steps = lhs.steps[..i].to_vec();
+    let rhs_terms = rhs.terms().cloned().collect_vec();
+    for k in (0..j).rev() {
+        steps.push((rhs.steps[k].0, rhs_terms[k].clone()));
+    }
+    Some(Derivation {
+        start: lhs.start,
+        steps,
+    })
+}
+
+fn rewrite(p: Process, laws: &[&[Law]], ctx: &Context, deep: bool) -> Derivation {
+    let mut derivation = Derivation {
+        start: p,
+        steps: vec![],
+    };
+    while derivation.steps.len() < MAX_STEPS {
+        let curr = derivation.result();
+        let Some(step) = laws.iter().find_map(|laws| step(curr, laws, ctx, deep)) else {
+            break;
+        };
+        derivation.steps.push(step);
+    }
+    derivation
+}
+
+fn step(p: &Process, laws: &[Law], ctx: &Context, deep: bool) -> Option<(&'static str, Process)> {
+    if let Some(step) = laws
+        .iter()
+        .find_map(|(name, law)| law(p, ctx).map(|q| (*name, q)))
+    {
+        return Some(step);
+    }
+    let step = |p: &Process| step(p, laws, ctx, deep);
+    match p {
+        Process::Constant(_, _) | Process::Skip => None,
+        Process::Action(ch, p, _) if deep => {
+            step(p).map(|(name, p)| (name, Process::action(ch.clone(), p)))
+        }
+        Process::Rated(ch, r, p, _) if deep => {
+            step(p).map(|(name, p)| (name, Process::rated(ch.clone(), *r, p)))
+        }
+        Process::Delay(n, p) if deep => step(p).map(|(name, p)| (name, Process::delay(*n, p))),
+        Process::Action(..) | Process::Rated(..) | Process::Delay(..) => None,
+        Process::Sum(sum) => sum.iter().enumerate().find_map(|(i, p)| {
+            step(p).map(|(name, p)| {
+                let mut sum = sum.clone();
+                sum[i] = p;
+                (name, Process::sum(sum))
+            })
+        }),
+        Process::Prob(branches) => branches.iter().enumerate().find_map(|(i, (w, p))| {
+            step(p).map(|(name, p)| {
+                let mut branches = branches.clone();
+                branches[i] = (*w, p);
+                (name, Process::Prob(branches))
+            })
+        }),
+        Process::Par(p, q) => step(p)
+            .map(|(name, p)| (name, Process::par(p, *q.clone())))
+            .or_else(|| step(q).map(|(name, q)| (name, Process::par(*p.clone(), q)))),
+        Process::Sync(p, chans, q) => step(p)
+            .map(|(name, p)| (name, Process::sync(p, chans.clone(), *q.clone())))
+            .or_else(|| {
+                step(q).map(|(name, q)| (name, Process::sync(*p.clone(), chans.clone(), q)))
+            }),
+        Process::Substitution(p, subs) => {
+            step(p).map(|(name, p)| (name, Process::substitution(p, subs.clone())))
+        }
+        Process::Restriction(p, chans, _) => {
+            step(p).map(|(name, p)| (name, Process::restriction(p, chans.clone())))
+        }
+        Process::Hiding(p, chans) => {
+            step(p).map(|(name, p)| (name, Process::hiding(p, chans.clone())))
+        }
+        Process::Buffered(p, chan, contents) => {
+            step(p).map(|(name, p)| (name, Process::buffered(p, chan.clone(), contents.clone())))
+        }
+        Process::Rec(x, p) if deep => step(p).map(|(name, p)| (name, Process::rec(x.clone(), p))),
+        Process::Rec(..) => None,
+        Process::Replication(p, copies) => step(p)
+            .map(|(name, p)| (name, Process::replication(p, copies.clone())))
+            .or_else(|| {
+                copies.iter().enumerate().find_map(|(i, c)| {
+                    step(c).map(|(name, c)| {
+                        let mut copies = copies.clone();
+                        copies[i] = c;
+                        (name, Process::replication(*p.clone(), copies))
+                    })
+                })
+            }),
+        Process::Disrupt(p, q) => step(p)
+            .map(|(name, p)| (name, Process::disrupt(p, *q.clone())))
+            .or_else(|| step(q).map(|(name, q)| (name, Process::disrupt(*p.clone(), q)))),
+        Process::Seq(p, q) => step(p)
+            .map(|(name, p)| (name, Process::seq(p, *q.clone())))
+            .or_else(|| {
+                deep.then(|| step(q))
+                    .flatten()
+                    .map(|(name, q)| (name, Process::seq(*p.clone(), q)))
+            }),
+    }
+}
+
+fn prefixes(p: &Process) -> Option<Vec<(Channel, Process)>> {
+    match p {
+        Process::Action(ch, p, _) => Some(vec![(ch.clone(), *p.clone())]),
+        Process::Sum(sum) if !sum.is_empty() => sum
+            .iter()
+            .map(|p| match p {
+                Process::Action(ch, p, _) => Some((ch.clone(), *p.clone())),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn sum_nil(p: &Process, _: &Context) -> Option<Process> {
+    let Process::Sum(sum) = p else { return None };
+    if sum.len() == 1 {
+        return Some(sum[0].clone());
+    }
+    sum.iter()
+        .any(|p| p.is_nil())
+        .then(|| Process::sum(sum.iter().filter(|p| !p.is_nil()).cloned().collect()))
+}
+fn sum_assoc(p: &Process, _: &Context) -> Option<Process> {
+    let Process::Sum(sum) = p else { return None };
+    sum.iter().any(|p| matches!(p, Process::Sum(_))).then(|| {
+        Process::sum(
+            sum.iter()
+                .flat_map(|p| match p {
+                    Process::Sum(sum) => sum.clone(),
+                    p => vec![p.clone()],
+                })
+                .collect(),
+        )
+    })
+}
+fn sum_idem(p: &Process, _: &Context) -> Option<Process> {
+    let Process::Sum(sum) = p else { return None };
+    let unique = sum.iter().unique().cloned().collect_vec();
+    (unique.len() != sum.len()).then(|| Process::sum(unique))
+}
+fn sum_comm(p: &Process, _: &Context) -> Option<Process> {
+    let Process::Sum(sum) = p else { return None };
+    let sorted = sum.iter().sorted().cloned().collect_vec();
+    (sorted != *sum).then(|| Process::sum(sorted))
+}
+fn par_nil(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        // a nil side keeps the Par from terminating, so it only goes when the other side cannot
+        Process::Par(p, q) if q.is_nil() && !p.may_terminate() => Some(*p.clone()),
+        Process::Par(p, q) if p.is_nil() && !q.may_terminate() => Some(*q.clone()),
+        _ => None,
+    }
+}
+fn par_skip(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Par(p, q) if **q == Process::Skip => Some(*p.clone()),
+        Process::Par(p, q) if **p == Process::Skip => Some(*q.clone()),
+        _ => None,
+    }
+}
+fn res_nil(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Restriction(p, _, _) if p.is_nil() => Some(Process::nil()),
+        _ => None,
+    }
+}
+fn res_res(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Restriction(p, outer, _) => match p.as_ref() {
+            Process::Restriction(p, inner, _) => Some(Process::restriction(
+                *p.clone(),
+                inner.iter().chain(outer).unique().cloned().collect(),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn res_prefix(p: &Process, ctx: &Context) -> Option<Process> {
+    match p {
+        Process::Restriction(_, chans, _) if chans.iter().any(|c| ctx.buffer_of(c).is_some()) => {
+            None
+        }
+        Process::Restriction(p, chans, _) => match p.as_ref() {
+            // a restricted broadcast is heard by no one, so it goes ahead silently
+            Process::Action(Channel::Send(name), p, _)
+                if chans.contains(name) && ctx.is_broadcast(name) =>
+            {
+                Some(Process::action(
+                    Channel::tau(),
+                    Process::restriction(*p.clone(), chans.clone()),
+                ))
+            }
+            Process::Action(ch, _, _) if chans.iter().any(|c| c == ch.name()) => {
+                Some(Process::nil())
+            }
+            Process::Action(ch, p, _) => Some(Process::action(
+                ch.clone(),
+                Process::restriction(*p.clone(), chans.clone()),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn res_sum(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Restriction(p, chans, _) => match p.as_ref() {
+            Process::Sum(sum) if !sum.is_empty() => Some(Process::sum(
+                sum.iter()
+                    .map(|p| Process::restriction(p.clone(), chans.clone()))
+                    .collect(),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn rel_nil(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Substitution(p, _) if p.is_nil() => Some(Process::nil()),
+        _ => None,
+    }
+}
+fn rel_rel(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Substitution(p, outer) => match p.as_ref() {
+            Process::Substitution(p, inner) => {
+                Some(Process::substitution(*p.clone(), compose(inner, outer)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn rel_prefix(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Substitution(p, subs) => match p.as_ref() {
+            Process::Action(ch, p, _) => Some(Process::action(
+                subs.replace_channel(ch.clone()),
+                Process::substitution(*p.clone(), subs.clone()),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn rel_sum(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Substitution(p, subs) => match p.as_ref() {
+            Process::Sum(sum) if !sum.is_empty() => Some(Process::sum(
+                sum.iter()
+                    .map(|p| Process::substitution(p.clone(), subs.clone()))
+                    .collect(),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn hide_nil(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Hiding(p, _) if p.is_nil() => Some(Process::nil()),
+        _ => None,
+    }
+}
+fn hide_prefix(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Hiding(p, chans) => match p.as_ref() {
+            Process::Action(ch, p, _) => {
+                let ch = if chans.iter().any(|c| c == ch.name()) {
+                    Channel::tau()
+                } else {
+                    ch.clone()
+                };
+                Some(Process::action(
+                    ch,
+                    Process::hiding(*p.clone(), chans.clone()),
+                ))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn hide_sum(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Hiding(p, chans) => match p.as_ref() {
+            Process::Sum(sum) if !sum.is_empty() => Some(Process::sum(
+                sum.iter()
+                    .map(|p| Process::hiding(p.clone(), chans.clone()))
+                    .collect(),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn seq_skip(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Seq(p, q) if **p == Process::Skip => Some(*q.clone()),
+        _ => None,
+    }
+}
+fn seq_nil(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Seq(p, _) if p.is_nil() => Some(Process::nil()),
+        _ => None,
+    }
+}
+fn seq_prefix(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Seq(p, q) => match p.as_ref() {
+            Process::Action(ch, p, _) => Some(Process::action(
+                ch.clone(),
+                Process::seq(*p.clone(), *q.clone()),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn seq_sum(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Seq(p, q) => match p.as_ref() {
+            Process::Sum(sum) if !sum.is_empty() => Some(Process::sum(
+                sum.iter()
+                    .map(|p| Process::seq(p.clone(), *q.clone()))
+                    .collect(),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+fn dis_nil(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Disrupt(p, q) if q.is_nil() => Some(*p.clone()),
+        Process::Disrupt(p, q) if p.is_nil() => Some(*q.clone()),
+        _ => None,
+    }
+}
+fn rep_nil(p: &Process, _: &Context) -> Option<Process> {
+    match p {
+        Process::Replication(p, copies) if p.is_nil() && copies.iter().all(Process::is_nil) => {
+            Some(Process::nil())
+        }
+        _ => None,
+    }
+}
+fn dis_expand(p: &Process, _: &Context) -> Option<Process> {
+    let Process::Disrupt(p, q) = p else {
+        return None;
+    };
+    let sum = prefixes(p)?
+        .into_iter()
+        .map(|(a, p)| Process::action(a, Process::disrupt(p, *q.clone())))
+        .chain([*q.clone()])
+        .collect();
+    Some(Process::sum(sum))
+}
+fn expansion(p: &Process, ctx: &Context) -> Option<Process> {
+    let Process::Par(p, q) = p else { return None };
+    let (ps, qs) = (prefixes(p)?, prefixes(q)?);
+    if ps
+        .iter()
+        .chain(&qs)
+        .any(|(ch, _)| ctx.is_broadcast(ch.name()) || ctx.buffer_of(ch.name()).is_some())
+    {
+        return None;
+    }
+    // as in Process::derive, moves that synchronise (tau with tau too) are not interleaved
+    let mut sum = vec![];
+    let mut psynched = vec![false; ps.len()];
+    let mut qsynched = vec![false; qs.len()];
+    for (i, (a, p2)) in ps.iter().enumerate() {
+        for (j, (b, q2)) in qs.iter().enumerate() {
+            if a.is_synched_with(b) {
+                sum.push(Process::action(
+                    Channel::tau(),
+                    Process::par(p2.clone(), q2.clone()),
+                ));
+                psynched[i] = true;
+                qsynched[j] = true;
+            }
+        }
+    }
+    for ((a, p2), _) in ps.iter().zip(&psynched).filter(|(_, s)| !**s) {
+        sum.push(Process::action(
+            a.clone(),
+            Process::par(p2.clone(), *q.clone()),
+        ));
+    }
+    for ((b, q2), _) in qs.iter().zip(&qsynched).filter(|(_, s)| !**s) {
+        sum.push(Process::action(
+            b.clone(),
+            Process::par(*p.clone(), q2.clone()),
+        ));
+    }
+    Some(Process::sum(sum))
+}
+fn unfold(p: &Process, ctx: &Context) -> Option<Process> {
+    match p {
+        Process::Constant(name, _) => ctx.get_process(name).cloned(),
+        Process::Rec(x, body) => Some(body.as_ref().clone().replace_var(x, p)),
+        _ => None,
+    }
+}
+
+fn compose(inner: &Substitution, outer: &Substitution) -> Substitution {
+    let mut pairs = inner
+        .pairs()
+        .iter()
+        .map(|(new, old)| (outer.replace(new.clone()), old.clone()))
+        .collect_vec();
+    for (new, old) in outer.pairs() {
+        if inner.pairs().iter().all(|(_, o)| o != old) {
+            pairs.push((new.clone(), old.clone()));
+        }
+    }
+    Substitution::new(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proves(source: &str, p: &str, q: &str) -> bool {
+        let ctx = Context::try_from(source).unwrap();
+        let get = |name| ctx.get_process(name).unwrap().clone();
+        prove(get(p), get(q), &ctx).is_some()
+    }
+
+    #[test]
+    fn expansion_does_not_interleave_moves_that_synchronise() {
+        let source = "fn P() { (a!.nil | a?.nil) }
+            fn Q() { tau.nil }
+            fn R() { ((a!.a?.nil + a?.a!.nil) + tau.nil) }";
+        assert!(proves(source, "P", "Q"));
+        assert!(!proves(source, "P", "R"));
+    }
+
+    #[test]
+    fn expansion_synchronises_tau_with_tau() {
+        let source = "fn P() { (tau.nil | tau.nil) }
+            fn Q() { tau.nil }";
+        assert!(proves(source, "P", "Q"));
+    }
+
+    #[test]
+    fn restricted_broadcasts_go_ahead_silently() {
+        let source = "#![broadcast(b)]
+            fn P() { (b!.c!.nil \\ {b}) }
+            fn Q() { tau.c!.nil }
+            fn R() { (b?.c!.nil \\ {b}) }
+            fn S() { nil }";
+        assert!(proves(source, "P", "Q"));
+        assert!(proves(source, "R", "S"));
+    }
+
+    #[test]
+    fn nil_stays_beside_a_process_that_may_terminate() {
+        let p = Process::par(
+            Process::action(Channel::send("a"), Process::skip()),
+            Process::nil(),
+        );
+        assert_eq!(*simplify(p.clone()).result(), p);
+    }
+}
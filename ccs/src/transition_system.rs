@@ -0,0 +1,55 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+pub trait TransitionSystem {
+    type State: Clone + Eq + Hash;
+    type Label: Clone + Eq + Hash;
+
+    fn initial(&self) -> Self::State;
+    fn successors(&self, s: &Self::State) -> Vec<(Self::Label, Self::State)>;
+    fn labels(&self, s: &Self::State) -> HashSet<Self::Label> {
+        self.successors(s).into_iter().map(|(l, _)| l).collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Graph<S, L> {
+    init: S,
+    edges: HashMap<S, Vec<(L, S)>>,
+}
+impl<S: Clone + Eq + Hash, L: Clone + Eq + Hash> Graph<S, L> {
+    pub fn new(init: S) -> Self {
+        Self {
+            init,
+            edges: HashMap::new(),
+        }
+    }
+    pub fn add_edge(&mut self, from: S, label: L, to: S) {
+        self.edges.entry(from).or_default().push((label, to));
+    }
+}
+impl<S: Clone + Eq + Hash, L: Clone + Eq + Hash> TransitionSystem for Graph<S, L> {
+    type State = S;
+    type Label = L;
+
+    fn initial(&self) -> S {
+        self.init.clone()
+    }
+    fn successors(&self, s: &S) -> Vec<(L, S)> {
+        self.edges.get(s).cloned().unwrap_or_default()
+    }
+}
+
+pub fn reachable<T: TransitionSystem>(ts: &T) -> HashSet<T::State> {
+    let init = ts.initial();
+    let mut seen = HashSet::from([init.clone()]);
+    let mut queue = VecDeque::from([init]);
+    while let Some(s) = queue.pop_front() {
+        for (_, next) in ts.successors(&s) {
+            if seen.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
@@ -1,24 +1,89 @@
+use std::fmt::Display;
+use std::hash::Hash;
+
 use cli_tables::Table;
+use itertools::Itertools;
 
+use crate::bisimulation::Bisimulation;
 use crate::context::Context;
-use crate::lts::{Bisimulation, Lts};
+use crate::game::{answers, Counterexample, Move};
+use crate::lts::Lts;
 use crate::process::Process;
+use crate::rewrite::Derivation;
+use crate::transition_system::TransitionSystem;
 
-pub fn print_bisimulation(b: &Bisimulation) {
+pub fn print_bisimulation<S, T>(b: &Bisimulation<S, T>)
+where
+    S: Display + Clone + Eq + Hash,
+    T: Display + Clone + Eq + Hash,
+{
     println!("Bisimulation:");
     draw_table(
-        vec!["State", "", "State"],
-        b.iter()
-            .map(|(s1, s2)| {
-                if s1 == s2 {
-                    vec![format!("{s1}"), format!("="), format!("{s2}")]
-                } else {
-                    vec![format!("{s1}"), format!("~"), format!("{s2}")]
-                }
+        vec!["Class", "States", "States"],
+        b.classes()
+            .iter()
+            .enumerate()
+            .map(|(id, (left, right))| {
+                vec![
+                    format!("{id}"),
+                    left.iter().join(", "),
+                    right.iter().join(", "),
+                ]
             })
             .collect(),
     );
 }
+pub fn print_game<T, U>(t: &T, u: &U, cex: &Counterexample<T::State, U::State, T::Label>)
+where
+    T: TransitionSystem,
+    U: TransitionSystem<Label = T::Label>,
+    T::State: Display,
+    U::State: Display,
+    T::Label: Display,
+{
+    fn print_rec<T, U>(
+        t: &T,
+        u: &U,
+        cex: &Counterexample<T::State, U::State, T::Label>,
+        pair: (T::State, U::State),
+        depth: usize,
+    ) where
+        T: TransitionSystem,
+        U: TransitionSystem<Label = T::Label>,
+        T::State: Display,
+        U::State: Display,
+        T::Label: Display,
+    {
+        let indent = "  ".repeat(depth);
+        let m = cex.attack(pair.0.clone(), pair.1.clone()).unwrap();
+        match m {
+            Move::Left(a, p2) => println!("{indent}Attacker: {} --{a}--> {p2}", pair.0),
+            Move::Right(b, q2) => println!("{indent}Attacker: {} --{b}--> {q2}", pair.1),
+        }
+        let answers = answers(t, u, &pair, m);
+        if answers.is_empty() {
+            match m {
+                Move::Left(a, _) => println!("{indent}  Defender: {} cannot do {a}", pair.1),
+                Move::Right(b, _) => println!("{indent}  Defender: {} cannot do {b}", pair.0),
+            }
+        }
+        for (p2, q2) in answers {
+            match m {
+                Move::Left(a, _) => println!("{indent}  Defender: {} --{a}--> {q2}", pair.1),
+                Move::Right(b, _) => println!("{indent}  Defender: {} --{b}--> {p2}", pair.0),
+            }
+            print_rec(t, u, cex, (p2, q2), depth + 2);
+        }
+    }
+    println!("Bisimulation game:");
+    print_rec(t, u, cex, cex.init().clone(), 0);
+}
+pub fn print_derivation(d: &Derivation) {
+    println!("  {}", d.start());
+    for (law, p) in d.steps() {
+        println!("= {p}    [{law}]");
+    }
+}
 pub fn print_transitions(lts: &Lts) {
     println!("Transitions:");
     draw_table(